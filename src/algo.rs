@@ -1,6 +1,13 @@
 use rayon::prelude::*; // 1.5.0
 
-use crate::config::*;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Duration;
+
+use crate::contingency::*;
+use crate::error::*;
+use crate::store::*;
 use crate::utils::*;
 
 use logregressor::model::*;
@@ -8,15 +15,35 @@ use logregressor::model::*;
 type SNP = usize;
 type Element = f64;
 type Matrix = (Vec<Element>, usize);
+type Genotypes = (Vec<u8>, usize);
 
 // logregressor params
 const LR_N_ITERS: usize = 500;
 const LR_LEARN_RATE: f64 = 0.1;
 
-// top proportion of solutions to boost pheromone vals for
-const PROPORTION_TO_SELECT: f64 = 0.05;
-// number of solutions to retain per round
-const N_SOLUTIONS_TO_RETAIN: usize = 10;
+// the reason the colony stopped searching
+#[derive(Debug, Clone, PartialEq)]
+pub enum StopReason {
+    MaxIters(usize),
+    NoImprovement(usize),
+    LowEntropy(f64),
+    TimeLimit(Duration),
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StopReason::MaxIters(n) => write!(f, "reached max_iters ({})", n),
+            StopReason::NoImprovement(n) => {
+                write!(f, "best score did not improve for {} iterations", n)
+            }
+            StopReason::LowEntropy(e) => write!(f, "pheromone entropy fell to {:.4}", e),
+            StopReason::TimeLimit(d) => {
+                write!(f, "wall-clock limit of {:.1}s reached", d.as_secs_f64())
+            }
+        }
+    }
+}
 
 // calculates the Chi-square test stat for a contingency table
 pub fn chi_square_test(contingency_table: &Matrix) -> f64 {
@@ -34,160 +61,177 @@ pub fn chi_square_test(contingency_table: &Matrix) -> f64 {
     chi_square
 }
 
-// Function used to calculate the Chi square test stat for a single path
-pub fn train_one_x2(idx: &usize, paths: &Vec<Vec<SNP>>, x: &Matrix, y: &Matrix) -> (usize, f64) {
-    let path = paths.get(idx.to_owned()).unwrap();
-    let subset: Matrix = column_subset(&x, &path);
-    let contingency_table: Matrix = build_contingency_table(&subset, &y);
-    let test_stat: f64 = chi_square_test(&contingency_table);
+// calculates the Chi square test stat for the SNPs in a path
+pub fn path_x2(path: &[SNP], x: &Genotypes, y: &Matrix) -> f64 {
+    let subset: Genotypes = column_subset(x, path);
+    let contingency_table: Matrix = build_contingency_table(&subset, y);
 
-    (idx.to_owned(), test_stat)
+    chi_square_test(&contingency_table)
 }
 
-// Trains a logistic regression model for a single path
-pub fn train_one(idx: &usize, paths: &Vec<Vec<SNP>>, x: &Matrix, y: &Matrix) -> (usize, f64) {
-    let path = paths.get(idx.to_owned()).unwrap();
-    let mut subset: Matrix = column_subset(&x, &path);
-
-    let int_term: Matrix = get_interactive_term(&subset);
-
-    subset = append_columns(&subset, &int_term);
-
-    let mut model = LogRegressor::new();
-    let loss = model.train(&subset, &y, LR_N_ITERS, LR_LEARN_RATE);
-
-    (idx.to_owned(), loss)
+// association test of a SNP set, with the number of samples in the
+// contingency table it was computed from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SetStats {
+    pub n_samples: usize,
+    pub statistic: f64,
+    pub df: usize,
+    pub p_value: f64,
 }
 
-// Ant colony optimization algorithm routine
-pub fn aco(params: &Config) {
-    // load data
-    let (x, y, header): (Matrix, Matrix, Vec<String>) = load_data(&params.algo.data_fp);
-
-    let num_snps = x.1;
-    //////
-    // Load parameters
-    // TODO move all this????
-    let mut num_ants = 2000;
-    if let Some(k) = &params.algo.num_ants {
-        num_ants = k.to_owned();
-    }
-
-    let mut epis_dim = 3;
-    if let Some(dim) = &params.algo.epis_dim {
-        epis_dim = dim.to_owned();
-    }
+pub fn path_stats(path: &[SNP], x: &Genotypes, y: &Matrix, test: &AssociationTest) -> SetStats {
+    let contingency_table: Matrix = build_contingency_table(&column_subset(x, path), y);
 
-    let mut threshold: f64 = 0.8;
-    if let Some(t_0) = &params.algo.t_0 {
-        threshold = t_0.to_owned();
-    }
-
-    let mut evap_coeff: f64 = 0.1;
-    if let Some(evap) = &params.algo.evap_coeff {
-        evap_coeff = evap.to_owned();
-    }
+    test.test_table(&contingency_table, path)
+}
 
-    let mut lambda: f64 = 2.0;
-    if let Some(lambda_in) = &params.algo.lambda {
-        lambda = lambda_in.to_owned();
-    }
+// Function used to calculate the Chi square test stat for a single path
+pub fn train_one_x2(idx: &usize, paths: &Vec<Vec<SNP>>, x: &Genotypes, y: &Matrix) -> (usize, f64) {
+    let path = paths.get(idx.to_owned()).unwrap();
 
-    let mut num_iters: usize = 50;
-    if let Some(num_iters_in) = &params.algo.max_iters {
-        num_iters = num_iters_in.to_owned();
-    }
+    (idx.to_owned(), path_x2(path, x, y))
+}
 
-    // retain the top solutions
-    // NOTE: this vec is named with the intention of using logistic regression
-    // as the objective, but is confusing when using Chi square test
-    let mut top_losses: Vec<(Vec<String>, Vec<SNP>, f64)> = Vec::new();
+// scores every SNP set in canonical_paths that is not already in the
+// cache and adds it. sets are only ever scored once per run
+pub fn score_new_paths(
+    canonical_paths: &[Vec<SNP>],
+    score_cache: &mut HashMap<Vec<SNP>, f64>,
+    x: &Genotypes,
+    y: &Matrix,
+) {
+    let mut unscored: Vec<Vec<SNP>> = canonical_paths
+        .iter()
+        .filter(|p| !score_cache.contains_key(*p))
+        .cloned()
+        .collect();
+
+    unscored.sort_unstable();
+    unscored.dedup();
+
+    let scores: Vec<(Vec<SNP>, f64)> = unscored
+        .into_par_iter()
+        .map(|p| {
+            let score = path_x2(&p, x, y);
+            (p, score)
+        })
+        .collect();
+
+    score_cache.extend(scores);
+}
 
-    // init pheromones matrix
-    let mut pheromones: Matrix = init_pheromones(num_snps);
+// trains a logistic regression model, with an interaction term, on the
+// SNPs in a path and returns its loss. samples missing a genotype in
+// the path are left out
+pub fn path_lr_loss(path: &[SNP], x: &Genotypes, y: &Matrix) -> Result<f64> {
+    let (codes, y) = complete_rows(&column_subset(x, path), y);
+    let mut subset: Matrix = genotype_values(&codes);
 
-    for _ in 0..num_iters {
-        // give each ant its first snp
-        let mut paths: Vec<Vec<SNP>> = init_ants(num_ants, num_snps, epis_dim);
+    let int_term: Matrix = get_interactive_term(&subset);
 
-        paths.par_iter_mut().for_each(|p| {
-            expand_path(p, &pheromones, epis_dim, threshold);
-        });
+    subset = append_columns(&subset, &int_term)?;
 
-        // A vec of indices for the paths, used later for mapping losses,
-        // could be done without this though if par_iter guarantees
-        // order (I think it does?)
-        let path_indices: Vec<usize> = (0..paths.len()).into_iter().collect();
+    let mut model = LogRegressor::new();
 
-        let par_iter = path_indices
-            .par_iter()
-            .map(|idx| train_one_x2(idx, &paths, &x, &y));
+    Ok(model.train(&subset, &y, LR_N_ITERS, LR_LEARN_RATE))
+}
 
-        let mut losses: Vec<(usize, f64)> = par_iter.collect();
+// Trains a logistic regression model for a single path
+pub fn train_one(
+    idx: &usize,
+    paths: &Vec<Vec<SNP>>,
+    x: &Genotypes,
+    y: &Matrix,
+) -> Result<(usize, f64)> {
+    let path = paths.get(idx.to_owned()).unwrap();
 
-        // sort losses
-        losses.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+    Ok((idx.to_owned(), path_lr_loss(path, x, y)?))
+}
 
-        for idx in 0..N_SOLUTIONS_TO_RETAIN {
-            let path: Vec<SNP> = paths.get(losses.get(idx).unwrap().0).unwrap().to_owned();
-            let snps: Vec<String> = path
-                .iter()
-                .map(|s| header.get(s.to_owned()).unwrap().to_owned())
-                .collect();
-            top_losses.push((snps, path, losses.get(idx).unwrap().1));
-        }
+// the objective functions a SNP set can be scored with
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Objective {
+    // Chi square test stat, higher is better. this is what aco uses
+    ChiSquare,
+    // logistic regression loss, lower is better
+    LogisticLoss,
+}
 
-        // select top proportion of solutions
-        let partition: usize = losses.len() * PROPORTION_TO_SELECT as usize;
+pub const ALL_OBJECTIVES: [Objective; 2] = [Objective::ChiSquare, Objective::LogisticLoss];
 
-        // update pheromones
-        for idx in 0..partition {
-            let this_path = paths.get(losses.get(idx).unwrap().0).unwrap();
-            update_pheromones(&mut pheromones, &this_path, &evap_coeff, &lambda, true);
+impl Objective {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Objective::ChiSquare => "chi_square",
+            Objective::LogisticLoss => "logistic_loss",
         }
+    }
 
-        for idx in partition..losses.len() {
-            let this_path = paths.get(losses.get(idx).unwrap().0).unwrap();
-            update_pheromones(&mut pheromones, &this_path, &evap_coeff, &lambda, false);
+    pub fn score(&self, path: &[SNP], x: &Genotypes, y: &Matrix) -> Result<f64> {
+        match self {
+            Objective::ChiSquare => Ok(path_x2(path, x, y)),
+            Objective::LogisticLoss => path_lr_loss(path, x, y),
         }
     }
+}
 
-    top_losses.sort_by(|a, b| b.2.partial_cmp(&a.2).unwrap());
-
-    // get the top Chi square test stats, this is unnecessary though
-    // if using Chi square test as the objective function
-    let mut top_chi_stats: Vec<(Vec<String>, f64)> = Vec::new();
-
-    for solution in top_losses.iter() {
-        let sol: &(Vec<String>, Vec<SNP>, f64) = solution;
-        let col_subset: Matrix = column_subset(&x, &sol.1);
-        let contingency_table: Matrix = build_contingency_table(&col_subset, &y);
-        let test_stat: f64 = chi_square_test(&contingency_table);
-
-        top_chi_stats.push((sol.0.to_owned(), test_stat));
+// inserts a solution into a list of the best top_n, which is kept
+// sorted best first
+fn insert_top_n(top: &mut Vec<(Vec<SNP>, f64)>, path: Vec<SNP>, score: f64, top_n: usize) {
+    if top.len() >= top_n && best_first(score, top.last().unwrap().1) != Ordering::Less {
+        return;
     }
 
-    top_chi_stats.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+    let pos: usize = top
+        .iter()
+        .position(|t| best_first(score, t.1) == Ordering::Less)
+        .unwrap_or(top.len());
+    top.insert(pos, (path, score));
+    top.truncate(top_n);
+}
 
-    println!("\nCHI SQUARE TEST STATS");
-    for idx in 0..30 {
-        let this_sol = top_chi_stats.get(idx).unwrap();
-        println!("Path: {:?}\tX2 test stat: {}", this_sol.0, this_sol.1);
+// scores every combination of epis_dim SNPs by Chi square test stat
+// and returns the top_n best. combinations are split across threads by
+// their first SNP
+pub fn exhaustive_search(
+    x: &Genotypes,
+    y: &Matrix,
+    epis_dim: usize,
+    top_n: usize,
+) -> Vec<(Vec<SNP>, f64)> {
+    let num_snps: usize = x.1;
+
+    if epis_dim == 0 || epis_dim > num_snps || top_n == 0 {
+        return Vec::new();
     }
 
-    // Check to see what the Chi square test stat is of the true
-    // solution (make sure GAMETES modeling is working correctly
-    let true_sol: Vec<SNP> = vec![x.1 - 3, x.1 - 2, x.1 - 1];
-    let mut col_subset: Matrix = column_subset(&x, &true_sol);
-    let contingency_table: Matrix = build_contingency_table(&col_subset, &y);
-    let test_stat: f64 = chi_square_test(&contingency_table);
-    println!("True sol test stat: {}", test_stat);
-
-//    let int_term: Matrix = get_interactive_term(&col_subset);
-
-//    col_subset = append_columns(&col_subset, &int_term);
-
-//    let mut model = LogRegressor::new();
-//    let loss = model.train(&col_subset, &y, LR_N_ITERS, LR_LEARN_RATE);
-//    println!("True sol loss: {}", loss);
+    (0..=(num_snps - epis_dim))
+        .into_par_iter()
+        .map(|first| {
+            let mut top: Vec<(Vec<SNP>, f64)> = Vec::with_capacity(top_n + 1);
+
+            // the remaining SNPs are chosen from those after first
+            let mut rest: Vec<SNP> = ((first + 1)..(first + epis_dim)).collect();
+
+            loop {
+                let mut path: Vec<SNP> = Vec::with_capacity(epis_dim);
+                path.push(first);
+                path.extend_from_slice(&rest);
+
+                let score: f64 = path_x2(&path, x, y);
+                insert_top_n(&mut top, path, score, top_n);
+
+                if !next_combination(&mut rest, num_snps) {
+                    break;
+                }
+            }
+
+            top
+        })
+        .reduce(Vec::new, |mut a, b| {
+            for (path, score) in b.into_iter() {
+                insert_top_n(&mut a, path, score, top_n);
+            }
+            a
+        })
 }
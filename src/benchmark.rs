@@ -0,0 +1,280 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::prelude::*;
+use std::io::BufWriter;
+use std::path::Path;
+use std::time::Instant;
+
+use crate::config::*;
+use crate::error::*;
+use crate::qc::*;
+use crate::runner::*;
+use crate::simulate::*;
+use crate::utils::*;
+
+type SNP = usize;
+type Element = f64;
+type Matrix = (Vec<Element>, usize);
+type Genotypes = (Vec<u8>, usize);
+
+// datasets to benchmark over, loaded one at a time so only one is in
+// memory
+#[derive(Debug, Clone)]
+pub enum BenchmarkData {
+    // paths of replicate datasets in the format load_data reads
+    Files(Vec<String>),
+    // replicates simulated with these parameters. replicate i uses
+    // seed + i if a seed is set
+    Simulated {
+        params: SimParams,
+        replicates: usize,
+    },
+}
+
+impl BenchmarkData {
+    // every file in dir, in name order
+    pub fn from_dir(dir: &str) -> Result<BenchmarkData> {
+        let io_err = |why| AntsnapError::io(dir, why);
+
+        let mut files: Vec<String> = Vec::new();
+        for entry in fs::read_dir(dir).map_err(io_err)? {
+            let path = entry.map_err(io_err)?.path();
+            if path.is_file() {
+                files.push(path.display().to_string());
+            }
+        }
+        files.sort();
+
+        if files.is_empty() {
+            return Err(AntsnapError::Usage(format!("{} has no datasets", dir)));
+        }
+
+        Ok(BenchmarkData::Files(files))
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            BenchmarkData::Files(files) => files.len(),
+            BenchmarkData::Simulated { replicates, .. } => *replicates,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // the name and data of replicate idx
+    pub fn load(&self, idx: usize) -> Result<(String, Genotypes, Matrix, Vec<String>)> {
+        match self {
+            BenchmarkData::Files(files) => {
+                let fp: &String = files.get(idx).unwrap();
+                let (x, y, header) = load_data(fp)?;
+
+                let name: String = Path::new(fp)
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_else(|| fp.to_owned());
+
+                Ok((name, x, y, header))
+            }
+            BenchmarkData::Simulated { params, .. } => {
+                let mut rep_params: SimParams = params.to_owned();
+                rep_params.seed = params.seed.map(|seed| seed.wrapping_add(idx as u64));
+
+                let data: SimulatedData = simulate(&rep_params)?;
+
+                Ok((format!("sim_{}", idx), data.x, data.y, data.header))
+            }
+        }
+    }
+}
+
+// how the colony did on a single replicate
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplicateResult {
+    pub name: String,
+    // 1-based rank of the true SNP set among the solutions the colony
+    // retained, None if it was never retained
+    pub true_rank: Option<usize>,
+    pub best_score: f64,
+    pub iterations: usize,
+    pub runtime_secs: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchmarkSummary {
+    pub replicates: usize,
+    pub top_n: usize,
+    // proportion of replicates with the true set ranked first
+    pub top_1_power: f64,
+    // proportion of replicates with the true set in the top top_n
+    pub top_n_power: f64,
+    // mean rank over the replicates where the true set was retained
+    pub mean_rank: Option<f64>,
+    // proportion of replicates where the true set was retained at all
+    pub found: f64,
+    pub mean_runtime_secs: f64,
+}
+
+// runs the colony on one dataset, after QC if any thresholds are set.
+// the true set comes from params.true_snps or the M0P SNPs in the
+// header
+pub fn benchmark_replicate(
+    name: &str,
+    x: Genotypes,
+    y: Matrix,
+    header: Vec<String>,
+    params: &ResolvedParams,
+) -> Result<ReplicateResult> {
+    let (x, header, _) = apply_qc(x, &y, header, params)?;
+
+    let true_path: Vec<SNP> = get_true_solution(&header, &params.true_snps).ok_or_else(|| {
+        AntsnapError::Usage(format!(
+            "{} has no ground truth, name the true SNPs with true_snps",
+            name
+        ))
+    })?;
+
+    let runner = AcoRunner::new(x, y, header, params)?;
+
+    let start = Instant::now();
+    let result: AcoResult = runner.run()?;
+    let runtime_secs: f64 = start.elapsed().as_secs_f64();
+
+    let true_rank: Option<usize> = result
+        .solutions
+        .iter()
+        .position(|s| s.path == true_path)
+        .map(|idx| idx + 1);
+
+    Ok(ReplicateResult {
+        name: name.to_owned(),
+        true_rank,
+        best_score: result.solutions.first().map_or(0.0, |s| s.score),
+        iterations: result.iterations.len(),
+        runtime_secs,
+    })
+}
+
+// runs the colony on every replicate, in order. on_result is called as
+// each replicate finishes
+pub fn run_benchmark<F>(
+    data: &BenchmarkData,
+    params: &ResolvedParams,
+    mut on_result: F,
+) -> Result<Vec<ReplicateResult>>
+where
+    F: FnMut(&ReplicateResult),
+{
+    let mut results: Vec<ReplicateResult> = Vec::with_capacity(data.len());
+
+    for idx in 0..data.len() {
+        let (name, x, y, header) = data.load(idx)?;
+        let result: ReplicateResult = benchmark_replicate(&name, x, y, header, params)?;
+
+        on_result(&result);
+        results.push(result);
+    }
+
+    Ok(results)
+}
+
+pub fn summarize(results: &[ReplicateResult], top_n: usize) -> BenchmarkSummary {
+    let n: f64 = results.len().max(1) as f64;
+    let ranks: Vec<usize> = results.iter().filter_map(|r| r.true_rank).collect();
+
+    let proportion = |max_rank: usize| ranks.iter().filter(|r| **r <= max_rank).count() as f64 / n;
+
+    BenchmarkSummary {
+        replicates: results.len(),
+        top_n,
+        top_1_power: proportion(1),
+        top_n_power: proportion(top_n),
+        mean_rank: if ranks.is_empty() {
+            None
+        } else {
+            Some(ranks.iter().sum::<usize>() as f64 / ranks.len() as f64)
+        },
+        found: ranks.len() as f64 / n,
+        mean_runtime_secs: results.iter().map(|r| r.runtime_secs).sum::<f64>() / n,
+    }
+}
+
+// writes one row per replicate as a TSV
+pub fn write_replicate_results(results: &[ReplicateResult], fp: &str) -> Result<()> {
+    let io_err = |why| AntsnapError::io(fp, why);
+    let mut writer = BufWriter::new(File::create(fp).map_err(io_err)?);
+
+    writeln!(
+        writer,
+        "replicate\ttrue_rank\tbest_score\titerations\truntime_secs"
+    )
+    .map_err(io_err)?;
+
+    for result in results.iter() {
+        let true_rank: String = match result.true_rank {
+            Some(rank) => rank.to_string(),
+            None => "NA".to_owned(),
+        };
+
+        writeln!(
+            writer,
+            "{}\t{}\t{}\t{}\t{}",
+            result.name, true_rank, result.best_score, result.iterations, result.runtime_secs
+        )
+        .map_err(io_err)?;
+    }
+
+    writer.flush().map_err(io_err)
+}
+
+// header of summary_fields
+pub const SUMMARY_FIELDS_HEADER: &str =
+    "replicates\ttop_n\ttop_1_power\ttop_n_power\tmean_rank\tfound\tmean_runtime_secs";
+pub const SUMMARY_HEADER: &str =
+    "label\treplicates\ttop_n\ttop_1_power\ttop_n_power\tmean_rank\tfound\tmean_runtime_secs";
+
+// appends the summary as a row labelled with label, so the same file
+// collects runs with different parameters or versions. the header is
+// written if the file is new or empty
+pub fn append_summary(summary: &BenchmarkSummary, label: &str, fp: &str) -> Result<()> {
+    let io_err = |why| AntsnapError::io(fp, why);
+
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(fp)
+        .map_err(io_err)?;
+    let is_new: bool = file.metadata().map_err(io_err)?.len() == 0;
+    let mut writer = BufWriter::new(file);
+
+    if is_new {
+        writeln!(writer, "{}", SUMMARY_HEADER).map_err(io_err)?;
+    }
+
+    writeln!(writer, "{}", summary_row(summary, label)).map_err(io_err)?;
+
+    writer.flush().map_err(io_err)
+}
+
+pub fn summary_row(summary: &BenchmarkSummary, label: &str) -> String {
+    format!("{}\t{}", label, summary_fields(summary))
+}
+
+// the summary's fields, tab separated, without a label
+pub fn summary_fields(summary: &BenchmarkSummary) -> String {
+    let mean_rank: String = match summary.mean_rank {
+        Some(rank) => format!("{:.2}", rank),
+        None => "NA".to_owned(),
+    };
+
+    format!(
+        "{}\t{}\t{:.3}\t{:.3}\t{}\t{:.3}\t{:.3}",
+        summary.replicates,
+        summary.top_n,
+        summary.top_1_power,
+        summary.top_n_power,
+        mean_rank,
+        summary.found,
+        summary.mean_runtime_secs
+    )
+}
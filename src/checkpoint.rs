@@ -0,0 +1,91 @@
+use rand_pcg::Pcg32;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::Path;
+
+use crate::error::*;
+
+type SNP = usize;
+type Element = f64;
+type Matrix = (Vec<Element>, usize);
+
+// everything needed to continue a run from the start of iteration `iter`
+#[derive(Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub iter: usize,
+    pub pheromones: Matrix,
+    // retained solutions as (canonical path, score)
+    pub top_solutions: Vec<(Vec<SNP>, f64)>,
+    pub times_found: Vec<(Vec<SNP>, usize)>,
+    // empty in checkpoints written before it was saved
+    #[serde(default)]
+    pub visit_counts: Vec<usize>,
+    pub best_score: f64,
+    pub iters_without_improvement: usize,
+    pub rng: Pcg32,
+    pub config_hash: u64,
+    pub data_hash: u64,
+}
+
+// writes a checkpoint, going through a temporary file so that a crash
+// while writing can't clobber the previous checkpoint
+pub fn save_checkpoint(checkpoint: &Checkpoint, fp: &str) -> Result<()> {
+    let tmp_fp = format!("{}.tmp", fp);
+
+    let serialized: String =
+        serde_json::to_string(checkpoint).map_err(|why| AntsnapError::Checkpoint {
+            path: fp.to_owned(),
+            msg: format!("could not serialize checkpoint: {}", why),
+        })?;
+
+    File::create(&tmp_fp)
+        .and_then(|mut file| file.write_all(serialized.as_bytes()))
+        .and_then(|_| fs::rename(&tmp_fp, fp))
+        .map_err(|why| AntsnapError::io(fp, why))
+}
+
+pub fn load_checkpoint(fp: &str) -> Result<Checkpoint> {
+    if !Path::new(fp).exists() {
+        return Err(AntsnapError::Checkpoint {
+            path: fp.to_owned(),
+            msg: "cannot resume, no checkpoint found".to_owned(),
+        });
+    }
+
+    let contents: String = fs::read_to_string(fp).map_err(|why| AntsnapError::io(fp, why))?;
+
+    serde_json::from_str(contents.as_str()).map_err(|why| AntsnapError::Checkpoint {
+        path: fp.to_owned(),
+        msg: format!("could not parse checkpoint: {}", why),
+    })
+}
+
+// refuses to resume from a checkpoint made with a different dataset
+// or different search parameters
+pub fn verify_checkpoint(
+    checkpoint: &Checkpoint,
+    fp: &str,
+    config_hash: u64,
+    data_hash: u64,
+) -> Result<()> {
+    let refuse = |msg: &str| AntsnapError::Checkpoint {
+        path: fp.to_owned(),
+        msg: msg.to_owned(),
+    };
+
+    if checkpoint.data_hash != data_hash {
+        return Err(refuse(
+            "cannot resume, the dataset has changed since the checkpoint was written",
+        ));
+    }
+
+    if checkpoint.config_hash != config_hash {
+        return Err(refuse(
+            "cannot resume, the config has changed since the checkpoint was written",
+        ));
+    }
+
+    Ok(())
+}
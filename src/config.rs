@@ -1,11 +1,21 @@
 use serde::Deserialize;
 use std::fs::File;
 use std::io::prelude::*;
-use std::path::PathBuf;
+use std::path::Path;
 
-use toml::from_str;
+use crate::contingency::*;
+use crate::error::*;
+use crate::significance::*;
+use crate::store::*;
 
-#[derive(Deserialize)]
+// largest supported interaction order, contingency tables have
+// 2 * 3^epis_dim cells
+pub const MAX_EPIS_DIM: usize = 10;
+
+// parameters as given in a config file, unset ones take their values
+// from ResolvedParams::default()
+#[derive(Deserialize, Clone, Default)]
+#[serde(deny_unknown_fields)]
 pub struct AlgoParams {
     pub init_pheromone_val: Option<f64>,
     pub t_0: Option<f64>,
@@ -14,50 +24,557 @@ pub struct AlgoParams {
     pub num_ants: Option<usize>,
     pub max_iters: Option<usize>,
     pub lambda: Option<f64>,
-    pub data_fp: String,
+    pub data_fp: Option<String>,
+    // binary copy of data_fp. it is read instead of data_fp if it exists,
+    // otherwise it is written after data_fp is parsed
+    pub cache_fp: Option<String>,
+    // how to read the columns of data_fp. the delimiter is whitespace,
+    // comma, tab or space. has_header is detected if not set, the
+    // phenotype is the last column if phenotype_col is not set, and
+    // sample_id_cols are skipped as genotypes. columns are header names
+    // or 1-based numbers
+    pub data_delimiter: Option<String>,
+    pub has_header: Option<bool>,
+    pub phenotype_col: Option<String>,
+    pub sample_id_cols: Option<Vec<String>>,
+    // convergence criteria, aco runs for max_iters if these are not set
+    pub patience: Option<usize>,
+    pub min_entropy: Option<f64>,
+    pub max_time_secs: Option<f64>,
+    // per-iteration trace output, written as JSONL if the path ends in
+    // .jsonl, otherwise CSV
+    pub trace_fp: Option<String>,
+    // names of the ground truth SNPs for simulated data
+    pub true_snps: Option<Vec<String>>,
+    // RNG seed, runs are reproducible when this is set
+    pub seed: Option<u64>,
+    // periodic checkpoints for resuming long runs
+    pub checkpoint_fp: Option<String>,
+    pub checkpoint_every: Option<usize>,
+    // final pheromone matrix and per-SNP importance outputs. the matrix
+    // is written as its top_edges strongest edges if set or if there
+    // are too many SNPs for a dense table
+    pub pheromone_fp: Option<String>,
+    pub importance_fp: Option<String>,
+    pub top_edges: Option<usize>,
+    // SNP quality control before the search, SNPs below any threshold
+    // that is set are removed and listed in qc_report_fp
+    pub min_call_rate: Option<f64>,
+    pub min_maf: Option<f64>,
+    pub min_hwe_p: Option<f64>,
+    pub qc_report_fp: Option<String>,
+    // LD handling. with max_ld_r2 set, SNPs in LD above it with a nearby
+    // SNP are pruned during QC, which needs positions from ld_map_fp.
+    // with path_max_r2 set, ants never put two SNPs in LD above it in
+    // the same path. ld_window_bp limits which pairs are checked when
+    // positions are given
+    pub ld_map_fp: Option<String>,
+    pub ld_window_bp: Option<u64>,
+    pub max_ld_r2: Option<f64>,
+    pub path_max_r2: Option<f64>,
+    // fraction of samples held out, stratified by case status, to
+    // replicate the top solutions on. the colony only searches the rest
+    pub holdout_fraction: Option<f64>,
+    // significance of the reported solutions. with alpha set, each is
+    // marked significant if its p-value corrected by correction, either
+    // bonferroni over every combination of epis_dim SNPs or fdr over the
+    // sets the colony evaluated, is below alpha. significant_only drops
+    // the rest from the report
+    pub alpha: Option<f64>,
+    pub correction: Option<String>,
+    pub significant_only: Option<bool>,
+    // how the reported statistics and p-values are computed. test_statistic
+    // is chi_square or g_test. genotype combinations with an expected count
+    // below min_expected are kept, dropped or merged into one as
+    // sparse_cells says, and the df follows the table that is left. with
+    // monte_carlo_iters set, p-values come from that many tables with the
+    // phenotypes permuted instead of the Chi square distribution
+    pub test_statistic: Option<String>,
+    pub sparse_cells: Option<String>,
+    pub min_expected: Option<f64>,
+    pub monte_carlo_iters: Option<usize>,
+}
+
+// algorithm parameters with defaults applied. this is the one place
+// defaults are defined, both the CLI and AcoRunner use it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedParams {
+    pub init_pheromone_val: f64,
+    pub t_0: f64,
+    pub evap_coeff: f64,
+    pub epis_dim: usize,
+    pub num_ants: usize,
+    pub max_iters: usize,
+    pub lambda: f64,
+    pub data_fp: Option<String>,
+    pub cache_fp: Option<String>,
+    pub data_delimiter: String,
+    pub has_header: Option<bool>,
+    pub phenotype_col: Option<String>,
+    pub sample_id_cols: Vec<String>,
+    pub patience: Option<usize>,
+    pub min_entropy: Option<f64>,
+    pub max_time_secs: Option<f64>,
+    pub trace_fp: Option<String>,
+    pub true_snps: Option<Vec<String>>,
+    pub seed: Option<u64>,
+    pub checkpoint_fp: Option<String>,
+    pub checkpoint_every: usize,
+    pub pheromone_fp: Option<String>,
+    pub importance_fp: Option<String>,
+    pub top_edges: Option<usize>,
+    pub min_call_rate: Option<f64>,
+    pub min_maf: Option<f64>,
+    pub min_hwe_p: Option<f64>,
+    pub qc_report_fp: Option<String>,
+    pub ld_map_fp: Option<String>,
+    pub ld_window_bp: u64,
+    pub max_ld_r2: Option<f64>,
+    pub path_max_r2: Option<f64>,
+    pub holdout_fraction: Option<f64>,
+    pub alpha: Option<f64>,
+    pub correction: String,
+    pub significant_only: bool,
+    pub test_statistic: String,
+    pub sparse_cells: String,
+    pub min_expected: f64,
+    pub monte_carlo_iters: Option<usize>,
+}
+
+impl Default for ResolvedParams {
+    fn default() -> ResolvedParams {
+        ResolvedParams {
+            init_pheromone_val: 1.0,
+            t_0: 0.8,
+            evap_coeff: 0.1,
+            epis_dim: 3,
+            num_ants: 1000,
+            max_iters: 40,
+            lambda: 2.0,
+            data_fp: None,
+            cache_fp: None,
+            data_delimiter: "whitespace".to_owned(),
+            has_header: None,
+            phenotype_col: None,
+            sample_id_cols: Vec::new(),
+            patience: None,
+            min_entropy: None,
+            max_time_secs: None,
+            trace_fp: None,
+            true_snps: None,
+            seed: None,
+            checkpoint_fp: None,
+            checkpoint_every: 1,
+            pheromone_fp: None,
+            importance_fp: None,
+            top_edges: None,
+            min_call_rate: None,
+            min_maf: None,
+            min_hwe_p: None,
+            qc_report_fp: None,
+            ld_map_fp: None,
+            ld_window_bp: 250_000,
+            max_ld_r2: None,
+            path_max_r2: None,
+            holdout_fraction: None,
+            alpha: None,
+            correction: "bonferroni".to_owned(),
+            significant_only: false,
+            test_statistic: "chi_square".to_owned(),
+            sparse_cells: "keep".to_owned(),
+            min_expected: 5.0,
+            monte_carlo_iters: None,
+        }
+    }
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
 pub struct Config {
     pub algo: AlgoParams,
 }
 
-pub fn load_config(file_path: &PathBuf) -> Config {
-    let mut file = File::open(file_path.as_path()).unwrap_or_else(|why| {
-        panic!(
-            "Could not open config file: {}, why: {}",
-            file_path.to_str().unwrap(),
-            why
-        );
-    });
+impl AlgoParams {
+    // applies defaults to every parameter that is not set
+    pub fn resolve(&self) -> ResolvedParams {
+        let defaults = ResolvedParams::default();
+
+        ResolvedParams {
+            init_pheromone_val: self
+                .init_pheromone_val
+                .unwrap_or(defaults.init_pheromone_val),
+            t_0: self.t_0.unwrap_or(defaults.t_0),
+            evap_coeff: self.evap_coeff.unwrap_or(defaults.evap_coeff),
+            epis_dim: self.epis_dim.unwrap_or(defaults.epis_dim),
+            num_ants: self.num_ants.unwrap_or(defaults.num_ants),
+            max_iters: self.max_iters.unwrap_or(defaults.max_iters),
+            lambda: self.lambda.unwrap_or(defaults.lambda),
+            data_fp: self.data_fp.to_owned().or(defaults.data_fp),
+            cache_fp: self.cache_fp.to_owned().or(defaults.cache_fp),
+            data_delimiter: self
+                .data_delimiter
+                .to_owned()
+                .unwrap_or(defaults.data_delimiter),
+            has_header: self.has_header.or(defaults.has_header),
+            phenotype_col: self.phenotype_col.to_owned().or(defaults.phenotype_col),
+            sample_id_cols: self
+                .sample_id_cols
+                .to_owned()
+                .unwrap_or(defaults.sample_id_cols),
+            patience: self.patience.or(defaults.patience),
+            min_entropy: self.min_entropy.or(defaults.min_entropy),
+            max_time_secs: self.max_time_secs.or(defaults.max_time_secs),
+            trace_fp: self.trace_fp.to_owned().or(defaults.trace_fp),
+            true_snps: self.true_snps.to_owned().or(defaults.true_snps),
+            seed: self.seed.or(defaults.seed),
+            checkpoint_fp: self.checkpoint_fp.to_owned().or(defaults.checkpoint_fp),
+            checkpoint_every: self.checkpoint_every.unwrap_or(defaults.checkpoint_every),
+            pheromone_fp: self.pheromone_fp.to_owned().or(defaults.pheromone_fp),
+            importance_fp: self.importance_fp.to_owned().or(defaults.importance_fp),
+            top_edges: self.top_edges.or(defaults.top_edges),
+            min_call_rate: self.min_call_rate.or(defaults.min_call_rate),
+            min_maf: self.min_maf.or(defaults.min_maf),
+            min_hwe_p: self.min_hwe_p.or(defaults.min_hwe_p),
+            qc_report_fp: self.qc_report_fp.to_owned().or(defaults.qc_report_fp),
+            ld_map_fp: self.ld_map_fp.to_owned().or(defaults.ld_map_fp),
+            ld_window_bp: self.ld_window_bp.unwrap_or(defaults.ld_window_bp),
+            max_ld_r2: self.max_ld_r2.or(defaults.max_ld_r2),
+            path_max_r2: self.path_max_r2.or(defaults.path_max_r2),
+            holdout_fraction: self.holdout_fraction.or(defaults.holdout_fraction),
+            alpha: self.alpha.or(defaults.alpha),
+            correction: self.correction.to_owned().unwrap_or(defaults.correction),
+            significant_only: self.significant_only.unwrap_or(defaults.significant_only),
+            test_statistic: self
+                .test_statistic
+                .to_owned()
+                .unwrap_or(defaults.test_statistic),
+            sparse_cells: self
+                .sparse_cells
+                .to_owned()
+                .unwrap_or(defaults.sparse_cells),
+            min_expected: self.min_expected.unwrap_or(defaults.min_expected),
+            monte_carlo_iters: self.monte_carlo_iters.or(defaults.monte_carlo_iters),
+        }
+    }
+
+    // rejects parameters that are out of range or contradict each other.
+    // checks that need the data, like epis_dim against the number of
+    // SNPs, are done by AcoRunner::new
+    pub fn validate(&self) -> Result<()> {
+        self.resolve().validate()?;
+
+        if self.checkpoint_every.is_some() && self.checkpoint_fp.is_none() {
+            return Err(AntsnapError::invalid_param(
+                "checkpoint_every",
+                "is set but checkpoint_fp is not, so no checkpoints would be written",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+// returns an error for name unless the parameter is valid
+fn check_param(name: &str, is_valid: bool, expected: &str) -> Result<()> {
+    if is_valid {
+        Ok(())
+    } else {
+        Err(AntsnapError::invalid_param(name, expected))
+    }
+}
+
+impl ResolvedParams {
+    pub fn validate(&self) -> Result<()> {
+        check_param(
+            "t_0",
+            (0.0..=1.0).contains(&self.t_0),
+            "must be between 0 and 1, it is compared against a uniform random number",
+        )?;
+        check_param(
+            "evap_coeff",
+            self.evap_coeff > 0.0 && self.evap_coeff <= 1.0,
+            "must be greater than 0 and at most 1",
+        )?;
+        check_param(
+            "epis_dim",
+            (2..=MAX_EPIS_DIM).contains(&self.epis_dim),
+            &format!("must be between 2 and {}", MAX_EPIS_DIM),
+        )?;
+        check_param("num_ants", self.num_ants > 0, "must be at least 1")?;
+        check_param("max_iters", self.max_iters > 0, "must be at least 1")?;
+        check_param(
+            "lambda",
+            self.lambda.is_finite() && self.lambda > 0.0,
+            "must be a positive number",
+        )?;
+        check_param(
+            "init_pheromone_val",
+            self.init_pheromone_val.is_finite() && self.init_pheromone_val > 0.0,
+            "must be a positive number",
+        )?;
+        check_param(
+            "patience",
+            self.patience.is_none_or(|v| v > 0),
+            "must be at least 1",
+        )?;
+        check_param(
+            "min_entropy",
+            self.min_entropy.is_none_or(|v| (0.0..=1.0).contains(&v)),
+            "must be between 0 and 1, pheromone entropy is normalized",
+        )?;
+        check_param(
+            "max_time_secs",
+            self.max_time_secs.is_none_or(|v| v.is_finite() && v > 0.0),
+            "must be a positive number of seconds",
+        )?;
+        check_param(
+            "checkpoint_every",
+            self.checkpoint_every > 0,
+            "must be at least 1",
+        )?;
+        check_param(
+            "top_edges",
+            self.top_edges.is_none_or(|v| v > 0),
+            "must be at least 1",
+        )?;
+
+        check_param(
+            "min_call_rate",
+            self.min_call_rate.is_none_or(|v| (0.0..=1.0).contains(&v)),
+            "must be between 0 and 1",
+        )?;
+        check_param(
+            "min_maf",
+            self.min_maf.is_none_or(|v| (0.0..=0.5).contains(&v)),
+            "must be between 0 and 0.5",
+        )?;
+        check_param(
+            "min_hwe_p",
+            self.min_hwe_p.is_none_or(|v| (0.0..=1.0).contains(&v)),
+            "must be between 0 and 1",
+        )?;
+
+        check_param(
+            "data_delimiter",
+            Delimiter::from_name(&self.data_delimiter).is_some(),
+            &format!("must be one of {}", DELIMITER_NAMES.join(", ")),
+        )?;
+
+        check_param("ld_window_bp", self.ld_window_bp > 0, "must be at least 1")?;
+        check_param(
+            "max_ld_r2",
+            self.max_ld_r2.is_none_or(|v| v > 0.0 && v <= 1.0),
+            "must be greater than 0 and at most 1",
+        )?;
+        check_param(
+            "path_max_r2",
+            self.path_max_r2.is_none_or(|v| v > 0.0 && v <= 1.0),
+            "must be greater than 0 and at most 1",
+        )?;
+        check_param(
+            "holdout_fraction",
+            self.holdout_fraction.is_none_or(|v| v > 0.0 && v < 1.0),
+            "must be between 0 and 1, exclusive",
+        )?;
+        check_param(
+            "alpha",
+            self.alpha.is_none_or(|v| v > 0.0 && v < 1.0),
+            "must be between 0 and 1, exclusive",
+        )?;
+        check_param(
+            "correction",
+            Correction::from_name(&self.correction).is_some(),
+            &format!("must be one of {}", CORRECTION_NAMES.join(", ")),
+        )?;
+        check_param(
+            "test_statistic",
+            TestStatistic::from_name(&self.test_statistic).is_some(),
+            &format!("must be one of {}", TEST_STATISTIC_NAMES.join(", ")),
+        )?;
+        check_param(
+            "sparse_cells",
+            SparseCells::from_name(&self.sparse_cells).is_some(),
+            &format!("must be one of {}", SPARSE_CELL_NAMES.join(", ")),
+        )?;
+        check_param("min_expected", self.min_expected > 0.0, "must be positive")?;
+        check_param(
+            "monte_carlo_iters",
+            self.monte_carlo_iters.is_none_or(|v| v > 0),
+            "must be at least 1",
+        )?;
+
+        if self.significant_only && self.alpha.is_none() {
+            return Err(AntsnapError::invalid_param(
+                "significant_only",
+                "is set but alpha is not, so nothing is tested for significance",
+            ));
+        }
+
+        if self.max_ld_r2.is_some() && self.ld_map_fp.is_none() {
+            return Err(AntsnapError::invalid_param(
+                "max_ld_r2",
+                "is set but ld_map_fp is not, LD pruning needs SNP positions",
+            ));
+        }
+
+        if self.top_edges.is_some() && self.pheromone_fp.is_none() {
+            return Err(AntsnapError::invalid_param(
+                "top_edges",
+                "is set but pheromone_fp is not, so the pheromones are not exported",
+            ));
+        }
+
+        if let Some(true_snps) = self.true_snps.as_ref() {
+            if true_snps.is_empty() {
+                return Err(AntsnapError::invalid_param(
+                    "true_snps",
+                    "must name at least one SNP",
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    // how data_fp is read. data_delimiter must already be validated
+    pub fn load_options(&self) -> LoadOptions {
+        LoadOptions {
+            delimiter: Delimiter::from_name(&self.data_delimiter).unwrap(),
+            has_header: self.has_header,
+            phenotype_col: self.phenotype_col.to_owned(),
+            sample_id_cols: self.sample_id_cols.to_owned(),
+        }
+    }
+
+    // how reported SNP sets are tested. test_statistic and sparse_cells
+    // must already be validated
+    pub fn association_test(&self) -> AssociationTest {
+        AssociationTest {
+            statistic: TestStatistic::from_name(&self.test_statistic).unwrap(),
+            sparse_cells: SparseCells::from_name(&self.sparse_cells).unwrap(),
+            min_expected: self.min_expected,
+            monte_carlo_iters: self.monte_carlo_iters,
+            seed: self.seed,
+        }
+    }
+
+    // name = value lines for every parameter
+    pub fn describe(&self) -> String {
+        let optional = |val: Option<String>| val.unwrap_or_else(|| "not set".to_owned());
+
+        let params: Vec<(&str, String)> = vec![
+            ("data_fp", optional(self.data_fp.to_owned())),
+            ("cache_fp", optional(self.cache_fp.to_owned())),
+            ("data_delimiter", self.data_delimiter.to_owned()),
+            (
+                "has_header",
+                optional(self.has_header.map(|v| v.to_string())),
+            ),
+            ("phenotype_col", optional(self.phenotype_col.to_owned())),
+            ("sample_id_cols", self.sample_id_cols.join(",")),
+            ("num_ants", self.num_ants.to_string()),
+            ("epis_dim", self.epis_dim.to_string()),
+            ("t_0", self.t_0.to_string()),
+            ("evap_coeff", self.evap_coeff.to_string()),
+            ("lambda", self.lambda.to_string()),
+            ("max_iters", self.max_iters.to_string()),
+            ("patience", optional(self.patience.map(|v| v.to_string()))),
+            (
+                "min_entropy",
+                optional(self.min_entropy.map(|v| v.to_string())),
+            ),
+            (
+                "max_time_secs",
+                optional(self.max_time_secs.map(|v| v.to_string())),
+            ),
+            ("seed", optional(self.seed.map(|v| v.to_string()))),
+            ("trace_fp", optional(self.trace_fp.to_owned())),
+            (
+                "true_snps",
+                optional(self.true_snps.as_ref().map(|v| v.join(","))),
+            ),
+            ("checkpoint_fp", optional(self.checkpoint_fp.to_owned())),
+            ("checkpoint_every", self.checkpoint_every.to_string()),
+            ("pheromone_fp", optional(self.pheromone_fp.to_owned())),
+            ("importance_fp", optional(self.importance_fp.to_owned())),
+            ("top_edges", optional(self.top_edges.map(|v| v.to_string()))),
+            (
+                "min_call_rate",
+                optional(self.min_call_rate.map(|v| v.to_string())),
+            ),
+            ("min_maf", optional(self.min_maf.map(|v| v.to_string()))),
+            ("min_hwe_p", optional(self.min_hwe_p.map(|v| v.to_string()))),
+            ("qc_report_fp", optional(self.qc_report_fp.to_owned())),
+            ("ld_map_fp", optional(self.ld_map_fp.to_owned())),
+            ("ld_window_bp", self.ld_window_bp.to_string()),
+            ("max_ld_r2", optional(self.max_ld_r2.map(|v| v.to_string()))),
+            (
+                "path_max_r2",
+                optional(self.path_max_r2.map(|v| v.to_string())),
+            ),
+            (
+                "holdout_fraction",
+                optional(self.holdout_fraction.map(|v| v.to_string())),
+            ),
+            ("alpha", optional(self.alpha.map(|v| v.to_string()))),
+            ("correction", self.correction.to_owned()),
+            ("significant_only", self.significant_only.to_string()),
+            ("test_statistic", self.test_statistic.to_owned()),
+            ("sparse_cells", self.sparse_cells.to_owned()),
+            ("min_expected", self.min_expected.to_string()),
+            (
+                "monte_carlo_iters",
+                optional(self.monte_carlo_iters.map(|v| v.to_string())),
+            ),
+        ];
+
+        params
+            .iter()
+            .map(|(name, val)| format!("{} = {}", name, val))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+pub fn load_config(file_path: &Path) -> Result<Config> {
+    let path_str: String = file_path.display().to_string();
+
     let mut contents = String::new();
 
-    file.read_to_string(&mut contents).unwrap_or_else(|why| {
-        panic!(
-            "Could not read config file: {}, why: {}",
-            file_path.to_str().unwrap(),
-            why
-        );
-    });
+    File::open(file_path)
+        .and_then(|mut file| file.read_to_string(&mut contents))
+        .map_err(|why| AntsnapError::io(&path_str, why))?;
+
+    let config: Config = toml::from_str(contents.as_str()).map_err(|why| {
+        let line_col: Option<(usize, usize)> = why.line_col();
+        AntsnapError::Config {
+            path: path_str.to_owned(),
+            // toml's line and column are 0-based
+            line: line_col.map(|lc| lc.0 + 1),
+            column: line_col.map(|lc| lc.1 + 1),
+            msg: why.to_string(),
+        }
+    })?;
 
-    toml::from_str(contents.as_str()).unwrap()
+    config.algo.validate()?;
+
+    Ok(config)
 }
 
+// a config with every parameter set to its default
 pub fn get_default_config() -> Config {
-    let config: Config = toml::from_str(
-        r#"
-    [algo]
-    init_pheromone_val = 1.0
-    t_0 = 0.8
-    evap_coeff = 0.1
-    epis_dim = 3
-    num_ants = 1000
-    max_iters = 40
-    lambda = 2.0
-    data_fp = 'data/gametes_small'
-    "#,
-    )
-    .unwrap();
-
-    config
+    let defaults = ResolvedParams::default();
+
+    Config {
+        algo: AlgoParams {
+            init_pheromone_val: Some(defaults.init_pheromone_val),
+            t_0: Some(defaults.t_0),
+            evap_coeff: Some(defaults.evap_coeff),
+            epis_dim: Some(defaults.epis_dim),
+            num_ants: Some(defaults.num_ants),
+            max_iters: Some(defaults.max_iters),
+            lambda: Some(defaults.lambda),
+            ..AlgoParams::default()
+        },
+    }
 }
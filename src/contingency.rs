@@ -0,0 +1,320 @@
+use rand::prelude::*;
+use rand::seq::SliceRandom;
+use rand_pcg::Pcg32;
+
+use crate::algo::*;
+use crate::stats::*;
+use crate::utils::*;
+
+type SNP = usize;
+type Element = f64;
+type Matrix = (Vec<Element>, usize);
+
+// the test stat computed from a contingency table
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TestStatistic {
+    // Pearson's Chi square test
+    ChiSquare,
+    // likelihood ratio test, also Chi square distributed
+    GTest,
+}
+
+pub const TEST_STATISTIC_NAMES: &[&str] = &["chi_square", "g_test"];
+
+impl TestStatistic {
+    pub fn from_name(name: &str) -> Option<TestStatistic> {
+        match name {
+            "chi_square" => Some(TestStatistic::ChiSquare),
+            "g_test" => Some(TestStatistic::GTest),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TestStatistic::ChiSquare => "chi_square",
+            TestStatistic::GTest => "g_test",
+        }
+    }
+
+    pub fn compute(&self, table: &Matrix) -> f64 {
+        match self {
+            TestStatistic::ChiSquare => chi_square_test(table),
+            TestStatistic::GTest => g_test(table),
+        }
+    }
+}
+
+// what happens to genotype combinations (table columns) with an
+// expected count below min_expected in any class
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SparseCells {
+    // left as they are
+    Keep,
+    // left out, along with their samples
+    Drop,
+    // pooled into a single column
+    Merge,
+}
+
+pub const SPARSE_CELL_NAMES: &[&str] = &["keep", "drop", "merge"];
+
+impl SparseCells {
+    pub fn from_name(name: &str) -> Option<SparseCells> {
+        match name {
+            "keep" => Some(SparseCells::Keep),
+            "drop" => Some(SparseCells::Drop),
+            "merge" => Some(SparseCells::Merge),
+            _ => None,
+        }
+    }
+}
+
+// how a SNP set's contingency table is tested. the colony always scores
+// sets by the Chi square test stat of the full table, this is used for
+// the statistics and p-values that are reported
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssociationTest {
+    pub statistic: TestStatistic,
+    pub sparse_cells: SparseCells,
+    pub min_expected: f64,
+    // p-values from this many tables with the phenotypes permuted, from
+    // the Chi square distribution if None
+    pub monte_carlo_iters: Option<usize>,
+    pub seed: Option<u64>,
+}
+
+impl Default for AssociationTest {
+    fn default() -> AssociationTest {
+        AssociationTest {
+            statistic: TestStatistic::ChiSquare,
+            sparse_cells: SparseCells::Keep,
+            min_expected: 5.0,
+            monte_carlo_iters: None,
+            seed: None,
+        }
+    }
+}
+
+impl AssociationTest {
+    // e.g. "g_test, sparse cells merged below 5, Monte Carlo p-value
+    // from 1000 tables"
+    pub fn describe(&self) -> String {
+        let mut parts: Vec<String> = vec![self.statistic.name().to_owned()];
+
+        match self.sparse_cells {
+            SparseCells::Keep => {}
+            SparseCells::Drop => {
+                parts.push(format!("sparse cells dropped below {}", self.min_expected))
+            }
+            SparseCells::Merge => {
+                parts.push(format!("sparse cells merged below {}", self.min_expected))
+            }
+        }
+
+        if let Some(iters) = self.monte_carlo_iters {
+            parts.push(format!("Monte Carlo p-value from {} tables", iters));
+        }
+
+        parts.join(", ")
+    }
+
+    // whether this is the default test, the Chi square distribution p-value
+    // of the full table. the seed is left out, it only matters for Monte
+    // Carlo p-values
+    pub fn is_plain(&self) -> bool {
+        let plain: AssociationTest = AssociationTest::default();
+
+        self.statistic == plain.statistic
+            && self.sparse_cells == plain.sparse_cells
+            && self.min_expected == plain.min_expected
+            && self.monte_carlo_iters == plain.monte_carlo_iters
+    }
+
+    // tests a contingency table. path only seeds the Monte Carlo RNG, so
+    // each SNP set gets its own permutations and a seeded run is
+    // reproducible regardless of the order sets are tested in
+    pub fn test_table(&self, table: &Matrix, path: &[SNP]) -> SetStats {
+        let table: Matrix = collapse_sparse_cells(table, self.min_expected, self.sparse_cells);
+
+        // nothing to test, e.g. every cell was sparse and dropped
+        if table.0.iter().all(|count| *count == 0.0) {
+            return SetStats {
+                n_samples: 0,
+                statistic: 0.0,
+                df: 0,
+                p_value: 1.0,
+            };
+        }
+
+        let statistic: f64 = self.statistic.compute(&table);
+        let df: usize = contingency_df(&table);
+
+        let p_value: f64 = match self.monte_carlo_iters {
+            Some(iters) => {
+                let mut rng: Pcg32 = match self.seed {
+                    Some(seed) => Pcg32::seed_from_u64(seed ^ path_hash(path)),
+                    None => Pcg32::from_entropy(),
+                };
+                monte_carlo_p(&table, self.statistic, iters, &mut rng)
+            }
+            None => chi_square_sf(statistic, df),
+        };
+
+        SetStats {
+            n_samples: table.0.iter().sum::<f64>() as usize,
+            statistic,
+            df,
+            p_value,
+        }
+    }
+}
+
+fn path_hash(path: &[SNP]) -> u64 {
+    let bytes: Vec<u8> = path
+        .iter()
+        .flat_map(|snp| (*snp as u64).to_le_bytes().to_vec())
+        .collect();
+
+    fnv1a_hash(&bytes)
+}
+
+// the G-test stat for a contingency table, 2 * sum(O * ln(O / E)).
+// empty cells contribute nothing
+pub fn g_test(contingency_table: &Matrix) -> f64 {
+    let expected_freqs: Matrix = get_expected_freqs(contingency_table);
+
+    let g: f64 = contingency_table
+        .0
+        .iter()
+        .zip(expected_freqs.0.iter())
+        .filter(|(observed, _)| **observed > 0.0)
+        .map(|(observed, expected)| observed * (observed / expected).ln())
+        .sum();
+
+    2.0 * g
+}
+
+// applies sparse_cells to the columns of a contingency table with an
+// expected count below min_expected. unless they are kept, empty columns
+// are left out too, they don't count towards the df either. a merged
+// column that is still sparse is added to the smallest column that isn't
+pub fn collapse_sparse_cells(
+    table: &Matrix,
+    min_expected: f64,
+    sparse_cells: SparseCells,
+) -> Matrix {
+    if sparse_cells == SparseCells::Keep {
+        return table.to_owned();
+    }
+
+    let n_cols: usize = table.1;
+    let n_rows: usize = table.0.len() / n_cols;
+    let total: f64 = table.0.iter().sum();
+    let row_totals: Vec<f64> = table.0.chunks(n_cols).map(|row| row.iter().sum()).collect();
+
+    let column =
+        |col: usize| -> Vec<f64> { (0..n_rows).map(|row| table.0[row * n_cols + col]).collect() };
+    let is_sparse = |counts: &[f64]| -> bool {
+        let col_total: f64 = counts.iter().sum();
+        row_totals
+            .iter()
+            .any(|row_total| row_total * col_total / total < min_expected)
+    };
+
+    let mut columns: Vec<Vec<f64>> = Vec::new();
+    let mut pooled: Vec<f64> = vec![0.0; n_rows];
+    let mut n_sparse: usize = 0;
+
+    for col in 0..n_cols {
+        let counts: Vec<f64> = column(col);
+
+        if counts.iter().sum::<f64>() == 0.0 {
+            continue;
+        }
+
+        if is_sparse(&counts) {
+            n_sparse += 1;
+            for (pooled_count, count) in pooled.iter_mut().zip(counts.iter()) {
+                *pooled_count += count;
+            }
+        } else {
+            columns.push(counts);
+        }
+    }
+
+    if sparse_cells == SparseCells::Merge && n_sparse > 0 {
+        let smallest: Option<usize> = (0..columns.len()).min_by(|a, b| {
+            let total_a: f64 = columns[*a].iter().sum();
+            let total_b: f64 = columns[*b].iter().sum();
+            total_a.total_cmp(&total_b)
+        });
+
+        match smallest {
+            Some(idx) if is_sparse(&pooled) => {
+                for (count, pooled_count) in columns[idx].iter_mut().zip(pooled.iter()) {
+                    *count += pooled_count;
+                }
+            }
+            _ => columns.push(pooled),
+        }
+    }
+
+    // a single empty column if nothing is left, which tests as nothing
+    if columns.is_empty() {
+        return (vec![0.0; n_rows], 1);
+    }
+
+    let mut table_out: Matrix = (Vec::with_capacity(n_rows * columns.len()), columns.len());
+    for row in 0..n_rows {
+        for counts in columns.iter() {
+            table_out.0.push(counts[row]);
+        }
+    }
+
+    table_out
+}
+
+// Monte Carlo estimate of the exact p-value of a contingency table: the
+// proportion of tables with the phenotypes shuffled, which keeps both
+// margins, with a stat at least as large as the observed one. the
+// observed table is counted so the estimate is never 0
+pub fn monte_carlo_p(
+    table: &Matrix,
+    statistic: TestStatistic,
+    iters: usize,
+    rng: &mut Pcg32,
+) -> f64 {
+    let n_cols: usize = table.1;
+    let observed: f64 = statistic.compute(table);
+    // allow for rounding when comparing against the observed stat
+    let threshold: f64 = observed - 1e-9 * observed.abs().max(1.0);
+
+    // the genotype column and class of each sample
+    let mut cols: Vec<usize> = Vec::new();
+    let mut classes: Vec<usize> = Vec::new();
+    for (idx, count) in table.0.iter().enumerate() {
+        for _ in 0..(*count as usize) {
+            cols.push(idx % n_cols);
+            classes.push(idx / n_cols);
+        }
+    }
+
+    let mut permuted: Matrix = (vec![0.0; table.0.len()], n_cols);
+    let mut n_extreme: usize = 0;
+
+    for _ in 0..iters {
+        classes.shuffle(rng);
+
+        permuted.0.iter_mut().for_each(|count| *count = 0.0);
+        for (col, class) in cols.iter().zip(classes.iter()) {
+            permuted.0[class * n_cols + col] += 1.0;
+        }
+
+        if statistic.compute(&permuted) >= threshold {
+            n_extreme += 1;
+        }
+    }
+
+    (n_extreme + 1) as f64 / (iters + 1) as f64
+}
@@ -0,0 +1,112 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+pub type Result<T> = std::result::Result<T, AntsnapError>;
+
+#[derive(Debug)]
+pub enum AntsnapError {
+    // a file could not be opened, read or written
+    Io {
+        path: String,
+        source: io::Error,
+    },
+    // the config file is not valid TOML or doesn't match AlgoParams
+    Config {
+        path: String,
+        line: Option<usize>,
+        column: Option<usize>,
+        msg: String,
+    },
+    // a problem with a data file, line and column are 1-based. column
+    // is the whitespace separated field, if the problem is with a field
+    Data {
+        path: String,
+        line: usize,
+        column: Option<usize>,
+        msg: String,
+    },
+    // a parameter that is out of range or contradicts another
+    InvalidParam {
+        name: String,
+        msg: String,
+    },
+    // a SNP name that isn't in the dataset's header
+    UnknownSnp(String),
+    // matrices with incompatible dimensions
+    Shape(String),
+    Checkpoint {
+        path: String,
+        msg: String,
+    },
+    // a binary dataset that can't be read
+    Cache {
+        path: String,
+        msg: String,
+    },
+    // invalid command line usage
+    Usage(String),
+}
+
+impl AntsnapError {
+    pub fn invalid_param(name: &str, msg: &str) -> AntsnapError {
+        AntsnapError::InvalidParam {
+            name: name.to_owned(),
+            msg: msg.to_owned(),
+        }
+    }
+
+    pub fn io(path: &str, source: io::Error) -> AntsnapError {
+        AntsnapError::Io {
+            path: path.to_owned(),
+            source,
+        }
+    }
+}
+
+impl fmt::Display for AntsnapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AntsnapError::Io { path, source } => write!(f, "{}: {}", path, source),
+            AntsnapError::Config {
+                path,
+                line,
+                column,
+                msg,
+            } => match (line, column) {
+                (Some(line), Some(column)) => {
+                    write!(f, "{}:{}:{}: invalid config: {}", path, line, column, msg)
+                }
+                _ => write!(f, "{}: invalid config: {}", path, msg),
+            },
+            AntsnapError::Data {
+                path,
+                line,
+                column,
+                msg,
+            } => match column {
+                Some(column) => write!(f, "{}:{}:{}: {}", path, line, column, msg),
+                None => write!(f, "{}:{}: {}", path, line, msg),
+            },
+            AntsnapError::InvalidParam { name, msg } => {
+                write!(f, "invalid value for {}: {}", name, msg)
+            }
+            AntsnapError::UnknownSnp(name) => write!(f, "unknown SNP: {}", name),
+            AntsnapError::Shape(msg) => write!(f, "{}", msg),
+            AntsnapError::Checkpoint { path, msg } => write!(f, "{}: {}", path, msg),
+            AntsnapError::Cache { path, msg } => {
+                write!(f, "{}: invalid binary dataset: {}", path, msg)
+            }
+            AntsnapError::Usage(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl Error for AntsnapError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AntsnapError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
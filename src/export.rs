@@ -0,0 +1,171 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::BufWriter;
+
+use crate::error::*;
+use crate::utils::*;
+
+type SNP = usize;
+type Element = f64;
+type Matrix = (Vec<Element>, usize);
+
+// above this many SNPs the pheromone matrix is written as a sparse list
+// of its strongest edges instead of a dense table
+pub const DENSE_EXPORT_MAX_SNPS: usize = 1000;
+// number of edges written in sparse form when top_edges is not set
+pub const DEFAULT_TOP_EDGES: usize = 10000;
+
+fn create_writer(fp: &str) -> Result<BufWriter<File>> {
+    let file = File::create(fp).map_err(|why| AntsnapError::io(fp, why))?;
+
+    Ok(BufWriter::new(file))
+}
+
+// an edge ordered from strongest to weakest, ties by (i, j)
+struct Edge(SNP, SNP, f64);
+
+impl PartialEq for Edge {
+    fn eq(&self, other: &Edge) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Edge {}
+
+impl PartialOrd for Edge {
+    fn partial_cmp(&self, other: &Edge) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Edge {
+    fn cmp(&self, other: &Edge) -> Ordering {
+        other
+            .2
+            .total_cmp(&self.2)
+            .then((self.0, self.1).cmp(&(other.0, other.1)))
+    }
+}
+
+// returns the top_n edges of the pheromone matrix as (i, j, pheromone)
+// with i < j, strongest first. only the top_n strongest so far are kept,
+// in a heap with the weakest of them on top
+pub fn top_pheromone_edges(pheromones: &Matrix, top_n: usize) -> Vec<(SNP, SNP, f64)> {
+    if top_n == 0 {
+        return Vec::new();
+    }
+
+    let n: usize = pheromones.1;
+    let mut heap: BinaryHeap<Edge> = BinaryHeap::with_capacity(top_n + 1);
+
+    for row in 0..n {
+        for col in (row + 1)..n {
+            heap.push(Edge(row, col, pheromones.0[row * n + col]));
+
+            if heap.len() > top_n {
+                heap.pop();
+            }
+        }
+    }
+
+    heap.into_sorted_vec()
+        .into_iter()
+        .map(|edge| (edge.0, edge.1, edge.2))
+        .collect()
+}
+
+// writes the pheromone matrix as a TSV. small matrices are written
+// dense with SNP names as row and column labels, large ones (or if
+// top_edges is set) as the strongest edges in snp_a, snp_b, pheromone
+// form
+pub fn write_pheromones(
+    pheromones: &Matrix,
+    header: &[String],
+    fp: &str,
+    top_edges: Option<usize>,
+) -> Result<()> {
+    let mut writer = create_writer(fp)?;
+    let io_err = |why| AntsnapError::io(fp, why);
+    let n: usize = pheromones.1;
+
+    if top_edges.is_none() && n <= DENSE_EXPORT_MAX_SNPS {
+        let names: Vec<&str> = header.iter().take(n).map(|s| s.as_str()).collect();
+        writeln!(writer, "\t{}", names.join("\t")).map_err(io_err)?;
+
+        for row in 0..n {
+            let vals: Vec<String> = pheromones.0[row * n..(row + 1) * n]
+                .iter()
+                .map(|v| v.to_string())
+                .collect();
+            writeln!(writer, "{}\t{}", header.get(row).unwrap(), vals.join("\t"))
+                .map_err(io_err)?;
+        }
+    } else {
+        writeln!(writer, "snp_a\tsnp_b\tpheromone").map_err(io_err)?;
+
+        let top_n: usize = top_edges.unwrap_or(DEFAULT_TOP_EDGES);
+
+        for (i, j, val) in top_pheromone_edges(pheromones, top_n).iter() {
+            writeln!(
+                writer,
+                "{}\t{}\t{}",
+                header.get(*i).unwrap(),
+                header.get(*j).unwrap(),
+                val
+            )
+            .map_err(io_err)?;
+        }
+    }
+
+    writer.flush().map_err(io_err)
+}
+
+// per-SNP importance as (snp, summed incident pheromone, visit count),
+// sorted by summed pheromone. visit_counts is how many ant paths
+// included each SNP over the whole run
+pub fn snp_importance(pheromones: &Matrix, visit_counts: &[usize]) -> Vec<(SNP, f64, usize)> {
+    let n: usize = pheromones.1;
+    let mut importance: Vec<(SNP, f64, usize)> = Vec::with_capacity(n);
+
+    for snp in 0..n {
+        let mut summed: f64 = 0.0;
+        for col in 0..n {
+            if col != snp {
+                summed += pheromones.0.get(snp * n + col).unwrap();
+            }
+        }
+
+        importance.push((snp, summed, visit_counts.get(snp).unwrap_or(&0).to_owned()));
+    }
+
+    importance.sort_by(|a, b| best_first(a.1, b.1));
+
+    importance
+}
+
+pub fn write_importance(
+    importance: &[(SNP, f64, usize)],
+    header: &[String],
+    fp: &str,
+) -> Result<()> {
+    let mut writer = create_writer(fp)?;
+    let io_err = |why| AntsnapError::io(fp, why);
+
+    writeln!(writer, "rank\tsnp\tsummed_pheromone\tvisit_count").map_err(io_err)?;
+
+    for (rank, (snp, summed, visits)) in importance.iter().enumerate() {
+        writeln!(
+            writer,
+            "{}\t{}\t{}\t{}",
+            rank + 1,
+            header.get(*snp).unwrap(),
+            summed,
+            visits
+        )
+        .map_err(io_err)?;
+    }
+
+    writer.flush().map_err(io_err)
+}
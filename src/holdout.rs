@@ -0,0 +1,111 @@
+use rand::prelude::*;
+use rand::seq::SliceRandom;
+use rand_pcg::Pcg32;
+
+use crate::algo::*;
+use crate::contingency::*;
+use crate::error::*;
+use crate::runner::*;
+use crate::utils::*;
+
+type SNP = usize;
+type Element = f64;
+type Matrix = (Vec<Element>, usize);
+type Genotypes = (Vec<u8>, usize);
+
+// samples the colony searches and samples held out to replicate what it
+// finds, as row indices in order
+#[derive(Debug, Clone, PartialEq)]
+pub struct HoldoutSplit {
+    pub discovery: Vec<usize>,
+    pub replication: Vec<usize>,
+}
+
+// holds out fraction of the controls and fraction of the cases, each
+// rounded, so both parts keep the case ratio of the whole dataset
+pub fn stratified_split(y: &Matrix, fraction: f64, seed: Option<u64>) -> Result<HoldoutSplit> {
+    let mut rng: Pcg32 = match seed {
+        Some(seed) => Pcg32::seed_from_u64(seed),
+        None => Pcg32::from_entropy(),
+    };
+
+    let mut discovery: Vec<usize> = Vec::with_capacity(y.0.len());
+    let mut replication: Vec<usize> = Vec::new();
+
+    for is_case in [false, true].iter() {
+        let mut rows: Vec<usize> = (0..y.0.len())
+            .filter(|row| (y.0[*row] != 0.0) == *is_case)
+            .collect();
+        rows.shuffle(&mut rng);
+
+        let n_held: usize = (rows.len() as f64 * fraction).round() as usize;
+        replication.extend_from_slice(&rows[..n_held]);
+        discovery.extend_from_slice(&rows[n_held..]);
+    }
+
+    if discovery.is_empty() || replication.is_empty() {
+        return Err(AntsnapError::invalid_param(
+            "holdout_fraction",
+            &format!(
+                "leaves {} samples for discovery and {} for replication",
+                discovery.len(),
+                replication.len()
+            ),
+        ));
+    }
+
+    discovery.sort_unstable();
+    replication.sort_unstable();
+
+    Ok(HoldoutSplit {
+        discovery,
+        replication,
+    })
+}
+
+impl HoldoutSplit {
+    // the genotypes and phenotypes of the discovery samples, then of the
+    // replication samples
+    pub fn apply(&self, x: &Genotypes, y: &Matrix) -> ((Genotypes, Matrix), (Genotypes, Matrix)) {
+        (
+            (
+                row_subset(x, &self.discovery),
+                row_subset(y, &self.discovery),
+            ),
+            (
+                row_subset(x, &self.replication),
+                row_subset(y, &self.replication),
+            ),
+        )
+    }
+}
+
+// a solution tested on the samples it was found in and on the held out
+// samples
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replication {
+    pub path: Vec<SNP>,
+    pub snps: Vec<String>,
+    pub times_found: usize,
+    pub discovery: SetStats,
+    pub replication: SetStats,
+}
+
+// re-tests each solution on the replication samples
+pub fn replicate_solutions(
+    solutions: &[Solution],
+    discovery: (&Genotypes, &Matrix),
+    replication: (&Genotypes, &Matrix),
+    test: &AssociationTest,
+) -> Vec<Replication> {
+    solutions
+        .iter()
+        .map(|solution| Replication {
+            path: solution.path.to_owned(),
+            snps: solution.snps.to_owned(),
+            times_found: solution.times_found,
+            discovery: path_stats(&solution.path, discovery.0, discovery.1, test),
+            replication: path_stats(&solution.path, replication.0, replication.1, test),
+        })
+        .collect()
+}
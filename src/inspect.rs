@@ -0,0 +1,166 @@
+use std::fmt;
+
+use crate::algo::*;
+use crate::contingency::*;
+use crate::error::*;
+use crate::stats::*;
+use crate::utils::*;
+
+type SNP = usize;
+type Element = f64;
+type Matrix = (Vec<Element>, usize);
+type Genotypes = (Vec<u8>, usize);
+
+// observed and expected counts for one genotype combination
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenotypeCell {
+    // genotype of each SNP, in the order the SNPs were given
+    pub genotypes: Vec<usize>,
+    pub controls: f64,
+    pub cases: f64,
+    pub expected_controls: f64,
+    pub expected_cases: f64,
+}
+
+impl GenotypeCell {
+    // proportion of samples with this genotype combination that are
+    // cases, None if no samples have it
+    pub fn penetrance(&self) -> Option<f64> {
+        let total: f64 = self.controls + self.cases;
+
+        if total > 0.0 {
+            Some(self.cases / total)
+        } else {
+            None
+        }
+    }
+}
+
+// everything known about a single SNP set
+#[derive(Debug, Clone)]
+pub struct SnpSetReport {
+    pub snps: Vec<String>,
+    pub path: Vec<SNP>,
+    pub cells: Vec<GenotypeCell>,
+    // samples with a genotype for every SNP in the set, the ones the
+    // statistics were computed from
+    pub n_samples: usize,
+    pub chi_square: f64,
+    pub df: usize,
+    pub p_value: f64,
+    // the set tested as configured, after any sparse cells are dropped
+    // or merged
+    pub test: AssociationTest,
+    pub test_stats: SetStats,
+    pub objectives: Vec<(Objective, f64)>,
+}
+
+// builds the report for the named SNPs. the contingency table is built
+// with the SNPs in the order given, so cells read the same way the
+// names were written
+pub fn inspect_snp_set(
+    x: &Genotypes,
+    y: &Matrix,
+    header: &[String],
+    names: &[String],
+    test: &AssociationTest,
+) -> Result<SnpSetReport> {
+    let path: Vec<SNP> = resolve_snp_names(header, names)?;
+
+    let mut distinct: Vec<SNP> = canonical_path(&path);
+    distinct.dedup();
+    if distinct.len() != path.len() {
+        return Err(AntsnapError::Usage(format!(
+            "SNP set {} names a SNP more than once",
+            names.join(",")
+        )));
+    }
+
+    let table: Matrix = build_contingency_table(&column_subset(x, &path), y);
+    let expected: Matrix = get_expected_freqs(&table);
+
+    let cells: Vec<GenotypeCell> = (0..table.1)
+        .map(|col| GenotypeCell {
+            genotypes: genotype_label(col, path.len())
+                .split('/')
+                .map(|g| g.parse::<usize>().unwrap())
+                .collect(),
+            controls: table.0.get(col).unwrap().to_owned(),
+            cases: table.0.get(table.1 + col).unwrap().to_owned(),
+            expected_controls: expected.0.get(col).unwrap().to_owned(),
+            expected_cases: expected.0.get(table.1 + col).unwrap().to_owned(),
+        })
+        .collect();
+
+    let chi_square: f64 = chi_square_test(&table);
+    let df: usize = contingency_df(&table);
+    let test_stats: SetStats = test.test_table(&table, &canonical_path(&path));
+
+    let mut objectives: Vec<(Objective, f64)> = Vec::with_capacity(ALL_OBJECTIVES.len());
+    for objective in ALL_OBJECTIVES.iter() {
+        objectives.push((objective.to_owned(), objective.score(&path, x, y)?));
+    }
+
+    Ok(SnpSetReport {
+        snps: names.to_vec(),
+        path,
+        n_samples: table.0.iter().sum::<f64>() as usize,
+        cells,
+        chi_square,
+        df,
+        p_value: chi_square_sf(chi_square, df),
+        test: test.to_owned(),
+        test_stats,
+        objectives,
+    })
+}
+
+impl fmt::Display for SnpSetReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "SNPs: {}", self.snps.join(","))?;
+        writeln!(f, "Samples used: {}", self.n_samples)?;
+        writeln!(f, "X2 test stat: {}", self.chi_square)?;
+        writeln!(f, "df: {}", self.df)?;
+        writeln!(f, "p-value: {:e}", self.p_value)?;
+
+        // the configured test is only shown if it differs from the above
+        if !self.test.is_plain() {
+            writeln!(f, "Test: {}", self.test.describe())?;
+            writeln!(f, "Test stat: {}", self.test_stats.statistic)?;
+            writeln!(f, "Test df: {}", self.test_stats.df)?;
+            writeln!(f, "Test p-value: {:e}", self.test_stats.p_value)?;
+            writeln!(f, "Test samples: {}", self.test_stats.n_samples)?;
+        }
+
+        for (objective, score) in self.objectives.iter() {
+            writeln!(f, "{}: {}", objective.name(), score)?;
+        }
+
+        writeln!(
+            f,
+            "\n{}\tcontrols\tcases\tpenetrance\texpected_controls\texpected_cases",
+            self.snps.join("/")
+        )?;
+
+        for cell in self.cells.iter() {
+            let genotypes: Vec<String> = cell.genotypes.iter().map(|g| g.to_string()).collect();
+            let penetrance: String = match cell.penetrance() {
+                Some(p) => format!("{:.4}", p),
+                None => "NA".to_owned(),
+            };
+
+            writeln!(
+                f,
+                "{}\t{}\t{}\t{}\t{:.2}\t{:.2}",
+                genotypes.join("/"),
+                cell.controls,
+                cell.cases,
+                penetrance,
+                cell.expected_controls,
+                cell.expected_cases
+            )?;
+        }
+
+        Ok(())
+    }
+}
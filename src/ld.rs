@@ -0,0 +1,203 @@
+use rayon::prelude::*; // 1.5.0
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::prelude::*;
+
+use crate::error::*;
+use crate::store::*;
+
+type SNP = usize;
+type Genotypes = (Vec<u8>, usize);
+
+// position of a SNP on the genome
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnpPosition {
+    pub chrom: String,
+    pub bp: u64,
+}
+
+// squared correlation of the genotypes of two SNPs, over the samples
+// with both called. 0 if either is constant over those samples
+pub fn r_squared(x: &Genotypes, a: SNP, b: SNP) -> f64 {
+    let mut n: f64 = 0.0;
+    let mut sum_a: f64 = 0.0;
+    let mut sum_b: f64 = 0.0;
+    let mut sum_aa: f64 = 0.0;
+    let mut sum_bb: f64 = 0.0;
+    let mut sum_ab: f64 = 0.0;
+
+    for row in x.0.chunks(x.1) {
+        if row[a] == MISSING_CODE || row[b] == MISSING_CODE {
+            continue;
+        }
+        let val_a: f64 = row[a] as f64;
+        let val_b: f64 = row[b] as f64;
+
+        n += 1.0;
+        sum_a += val_a;
+        sum_b += val_b;
+        sum_aa += val_a * val_a;
+        sum_bb += val_b * val_b;
+        sum_ab += val_a * val_b;
+    }
+
+    let cov: f64 = sum_ab - sum_a * sum_b / n;
+    let var_a: f64 = sum_aa - sum_a * sum_a / n;
+    let var_b: f64 = sum_bb - sum_b * sum_b / n;
+
+    if n < 2.0 || var_a <= 0.0 || var_b <= 0.0 {
+        return 0.0;
+    }
+
+    (cov * cov) / (var_a * var_b)
+}
+
+// reads SNP positions from a PLINK .bim (chrom, id, cM, bp, a1, a2) or
+// .map (chrom, id, cM, bp) file and returns the position of each SNP in
+// header. every SNP in header must be in the file
+pub fn load_positions(fp: &str, header: &[String], num_snps: usize) -> Result<Vec<SnpPosition>> {
+    let mut contents = String::new();
+    File::open(fp)
+        .and_then(|mut file| file.read_to_string(&mut contents))
+        .map_err(|why| AntsnapError::io(fp, why))?;
+
+    let mut positions: HashMap<String, SnpPosition> = HashMap::new();
+
+    for (line_idx, line) in contents.lines().enumerate() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.is_empty() {
+            continue;
+        }
+
+        let data_err = |column: Option<usize>, msg: String| AntsnapError::Data {
+            path: fp.to_owned(),
+            line: line_idx + 1,
+            column,
+            msg,
+        };
+
+        if fields.len() < 4 {
+            return Err(data_err(
+                None,
+                format!("expected at least 4 fields, found {}", fields.len()),
+            ));
+        }
+
+        let bp: u64 = fields[3].parse::<u64>().map_err(|_| {
+            data_err(
+                Some(4),
+                format!("could not parse '{}' to a position", fields[3]),
+            )
+        })?;
+
+        positions.insert(
+            fields[1].to_owned(),
+            SnpPosition {
+                chrom: fields[0].to_owned(),
+                bp,
+            },
+        );
+    }
+
+    header
+        .iter()
+        .take(num_snps)
+        .map(|name| {
+            positions.get(name).cloned().ok_or_else(|| {
+                AntsnapError::Usage(format!("SNP {} is not in the map file {}", name, fp))
+            })
+        })
+        .collect()
+}
+
+// whether two SNPs are close enough for their LD to be checked. without
+// positions every pair is checked
+fn in_window(positions: Option<&[SnpPosition]>, a: SNP, b: SNP, window_bp: u64) -> bool {
+    match positions {
+        Some(positions) => {
+            let pos_a: &SnpPosition = &positions[a];
+            let pos_b: &SnpPosition = &positions[b];
+            pos_a.chrom == pos_b.chrom
+                && pos_a.bp.max(pos_b.bp) - pos_a.bp.min(pos_b.bp) <= window_bp
+        }
+        None => true,
+    }
+}
+
+// greedy LD pruning among the SNPs in candidates. SNPs are visited in
+// genome order and one is removed if its r^2 with an earlier kept SNP
+// on the same chromosome, within window_bp, is above max_r2. returns the
+// removed SNPs with their largest r^2
+pub fn ld_prune(
+    x: &Genotypes,
+    candidates: &[SNP],
+    positions: &[SnpPosition],
+    window_bp: u64,
+    max_r2: f64,
+) -> Vec<(SNP, f64)> {
+    let mut order: Vec<SNP> = candidates.to_vec();
+    order.sort_by(|a, b| {
+        positions[*a]
+            .chrom
+            .cmp(&positions[*b].chrom)
+            .then(positions[*a].bp.cmp(&positions[*b].bp))
+    });
+
+    let mut kept: Vec<SNP> = Vec::with_capacity(order.len());
+    let mut removed: Vec<(SNP, f64)> = Vec::new();
+
+    for snp in order.into_iter() {
+        // kept SNPs are in genome order, so the ones in the window are
+        // at the end
+        let max_linked: f64 = kept
+            .iter()
+            .rev()
+            .take_while(|other| in_window(Some(positions), snp, **other, window_bp))
+            .map(|other| r_squared(x, snp, *other))
+            .fold(0.0, f64::max);
+
+        if max_linked > max_r2 {
+            removed.push((snp, max_linked));
+        } else {
+            kept.push(snp);
+        }
+    }
+
+    removed.sort_by_key(|r| r.0);
+
+    removed
+}
+
+// for every SNP, the SNPs it has r^2 above max_r2 with, in order. only
+// pairs within window_bp are checked if positions are given, otherwise
+// every pair is. r^2 is symmetric, so each pair is computed once
+pub fn ld_partners(
+    x: &Genotypes,
+    positions: Option<&[SnpPosition]>,
+    window_bp: u64,
+    max_r2: f64,
+) -> Vec<Vec<SNP>> {
+    let later_partners: Vec<Vec<SNP>> = (0..x.1)
+        .into_par_iter()
+        .map(|snp| {
+            ((snp + 1)..x.1)
+                .filter(|other| {
+                    in_window(positions, snp, *other, window_bp)
+                        && r_squared(x, snp, *other) > max_r2
+                })
+                .collect()
+        })
+        .collect();
+
+    // mirrored in order: a SNP's earlier partners are added before its
+    // own turn, which adds the later ones
+    let mut partners: Vec<Vec<SNP>> = vec![Vec::new(); x.1];
+    for (snp, others) in later_partners.iter().enumerate() {
+        for other in others.iter() {
+            partners[snp].push(*other);
+            partners[*other].push(snp);
+        }
+    }
+
+    partners
+}
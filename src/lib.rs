@@ -1,15 +1,50 @@
 pub mod algo;
+pub mod benchmark;
+pub mod checkpoint;
 pub mod config;
+pub mod contingency;
+pub mod error;
+pub mod export;
+pub mod holdout;
+pub mod inspect;
+pub mod ld;
+pub mod qc;
+pub mod runner;
+pub mod significance;
+pub mod simulate;
+pub mod stability;
+pub mod stats;
+pub mod store;
+pub mod sweep;
+pub mod trace;
 pub mod utils;
 
 #[cfg(test)]
 mod tests {
     use crate::algo::*;
+    use crate::benchmark::*;
+    use crate::checkpoint::*;
     use crate::config::*;
+    use crate::contingency::*;
+    use crate::error::*;
+    use crate::export::*;
+    use crate::holdout::*;
+    use crate::inspect::*;
+    use crate::ld::*;
+    use crate::qc::*;
+    use crate::runner::*;
+    use crate::significance::*;
+    use crate::simulate::*;
+    use crate::stability::*;
+    use crate::stats::*;
+    use crate::store::*;
+    use crate::sweep::*;
+    use crate::trace::*;
     use crate::utils::*;
 
     type Element = f64;
     type Matrix = (Vec<Element>, usize);
+    type Genotypes = (Vec<u8>, usize);
 
     #[test]
     fn get_col_0() {
@@ -29,7 +64,7 @@ mod tests {
         let b_vals = vec![1.2, 2.3, 3.4, 4.5, 5.6, 6.7];
         let b: Matrix = (b_vals, 3);
 
-        let result = append_columns(&a, &b);
+        let result = append_columns(&a, &b).unwrap();
         let e_vals = vec![1.2, 2.3, 3.4, 1.2, 2.3, 3.4, 4.5, 5.6, 6.7, 4.5, 5.6, 6.7];
         let expected = (e_vals, 6);
 
@@ -100,4 +135,1290 @@ mod tests {
 
         assert_eq!(actual, expect);
     }
+
+    #[test]
+    fn canonical_path_0() {
+        let a: Vec<usize> = vec![9, 1, 5];
+        let b: Vec<usize> = vec![5, 9, 1];
+
+        assert_eq!(canonical_path(&a), vec![1, 5, 9]);
+        assert_eq!(canonical_path(&a), canonical_path(&b));
+    }
+
+    #[test]
+    fn score_new_paths_0() {
+        let x: Genotypes = (vec![0, 1, 2, 1, 2, 0, 2, 0, 1], 3);
+        let y: Matrix = (vec![0.0, 1.0, 1.0], 1);
+        let paths: Vec<Vec<usize>> = vec![vec![0, 1, 2], vec![0, 1, 2]];
+
+        let mut cache: std::collections::HashMap<Vec<usize>, f64> =
+            std::collections::HashMap::new();
+        cache.insert(vec![0, 1, 2], -1.0);
+
+        // already scored sets are not rescored
+        score_new_paths(&paths, &mut cache, &x, &y);
+
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.get(&vec![0, 1, 2]), Some(&-1.0));
+    }
+
+    #[test]
+    fn pheromone_entropy_0() {
+        let uniform: Matrix = init_pheromones(4);
+        assert!((pheromone_entropy(&uniform) - 1.0).abs() < 1e-12);
+
+        let mut concentrated: Matrix = init_pheromones(4);
+        update_pheromones(&mut concentrated, &vec![0, 1], &0.5, &100.0, true);
+        assert!(pheromone_entropy(&concentrated) < pheromone_entropy(&uniform));
+    }
+
+    #[test]
+    fn get_true_solution_0() {
+        let header: Vec<String> = vec!["N0", "M0P1", "N1", "M0P0", "Class"]
+            .into_iter()
+            .map(|s| s.to_owned())
+            .collect();
+
+        assert_eq!(get_true_solution(&header, &None), Some(vec![1, 3]));
+
+        let named = Some(vec!["N1".to_owned(), "N0".to_owned()]);
+        assert_eq!(get_true_solution(&header, &named), Some(vec![0, 2]));
+
+        let missing = Some(vec!["N5".to_owned()]);
+        assert_eq!(get_true_solution(&header, &missing), None);
+    }
+
+    #[test]
+    fn fnv1a_hash_0() {
+        assert_eq!(fnv1a_hash(b""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a_hash(b"a"), 0xaf63dc4c8601ec8c);
+        assert_eq!(fnv1a_hash(b"foobar"), 0x85944171f73967e8);
+
+        // hashing a dataset in pieces matches hashing it in one buffer, so
+        // data hashes in existing checkpoints still match
+        let x: Genotypes = (vec![0, 1, 2, 1], 2);
+        let y: Matrix = (vec![1.0, 0.0], 1);
+        let header: Vec<String> = vec!["N0".to_owned(), "N1".to_owned()];
+
+        // genotypes are hashed as f64 values, as they were before they
+        // were kept as codes
+        let mut bytes: Vec<u8> = 2u64.to_le_bytes().to_vec();
+        for val in x.0.iter().map(|g| *g as f64).chain(y.0.iter().cloned()) {
+            bytes.extend_from_slice(&val.to_le_bytes());
+        }
+        bytes.extend_from_slice(b"N0\tN1\t");
+
+        assert_eq!(hash_dataset(&x, &y, &header), fnv1a_hash(&bytes));
+    }
+
+    #[test]
+    fn expand_path_seeded_0() {
+        use rand::SeedableRng;
+
+        let pheromones: Matrix = init_pheromones(20);
+
+        let mut rng_a = rand_pcg::Pcg32::seed_from_u64(42);
+        let mut rng_b = rand_pcg::Pcg32::seed_from_u64(42);
+
+        let mut path_a: Vec<usize> = vec![0];
+        let mut path_b: Vec<usize> = vec![0];
+        expand_path(&mut path_a, &pheromones, 4, 0.8, &mut rng_a);
+        expand_path(&mut path_b, &pheromones, 4, 0.8, &mut rng_b);
+
+        assert_eq!(path_a.len(), 4);
+        assert_eq!(path_a, path_b);
+    }
+
+    #[test]
+    fn snp_importance_0() {
+        let mut pheromones: Matrix = init_pheromones(3);
+        update_pheromones(&mut pheromones, &vec![0, 2], &0.5, &4.0, true);
+
+        let importance = snp_importance(&pheromones, &[1, 0, 3]);
+
+        // SNP 1 only has its two unreinforced edges
+        assert_eq!(importance.last().unwrap(), &(1, 2.0, 0));
+        assert_eq!(importance.first().unwrap().1, 3.5);
+
+        let edges = top_pheromone_edges(&pheromones, 1);
+        assert_eq!(edges, vec![(0, 2, 2.5)]);
+
+        // ties are broken by position
+        let edges = top_pheromone_edges(&pheromones, 5);
+        assert_eq!(edges, vec![(0, 2, 2.5), (0, 1, 1.0), (1, 2, 1.0)]);
+        assert!(top_pheromone_edges(&pheromones, 0).is_empty());
+    }
+
+    // a small dataset where the phenotype is fully determined by
+    // SNPs 1, 3 and 4
+    fn toy_dataset() -> (Genotypes, Matrix, Vec<String>) {
+        let n_snps: usize = 6;
+        let mut x: Genotypes = (Vec::new(), n_snps);
+        let mut y: Matrix = (Vec::new(), 1);
+
+        for row in 0..120 {
+            let genotypes: Vec<u8> = (0..n_snps)
+                .map(|col| ((row * (col + 2) + row / (col + 1)) % 3) as u8)
+                .collect();
+            let status: f64 = ((genotypes[1] + genotypes[3] + genotypes[4]) as usize % 2) as f64;
+
+            x.0.extend(genotypes);
+            y.0.push(status);
+        }
+
+        let header: Vec<String> = (0..n_snps).map(|i| format!("N{}", i)).collect();
+
+        (x, y, header)
+    }
+
+    #[test]
+    fn aco_runner_0() {
+        let (x, y, header) = toy_dataset();
+        let mut params = ResolvedParams::default();
+        params.num_ants = 30;
+        params.max_iters = 5;
+        params.seed = Some(3);
+
+        let result_a = AcoRunner::new(x.clone(), y.clone(), header.clone(), &params)
+            .unwrap()
+            .run()
+            .unwrap();
+        let result_b = AcoRunner::new(x, y, header, &params)
+            .unwrap()
+            .run()
+            .unwrap();
+
+        assert_eq!(result_a.iterations.len(), 5);
+        assert_eq!(result_a.solutions, result_b.solutions);
+        assert_eq!(result_a.solutions.first().unwrap().path, vec![1, 3, 4]);
+
+        for pair in result_a.solutions.windows(2) {
+            assert!(pair[0].score >= pair[1].score);
+        }
+    }
+
+    // writes contents to a file in the temp dir, returning its path
+    fn write_temp_file(name: &str, contents: &str) -> String {
+        let path = std::env::temp_dir().join(format!("antsnap_test_{}", name));
+        std::fs::write(&path, contents).unwrap();
+
+        path.to_str().unwrap().to_owned()
+    }
+
+    #[test]
+    fn load_data_0() {
+        let fp = write_temp_file("load_data_0", "N0\tN1\tClass\n0\t2\t1\n1\t1\t0\n");
+
+        let (x, y, header) = load_data(&fp).unwrap();
+
+        assert_eq!(x, (vec![0, 2, 1, 1], 2));
+        assert_eq!(y, (vec![1.0, 0.0], 1));
+        assert_eq!(header, vec!["N0", "N1", "Class"]);
+    }
+
+    #[test]
+    fn load_data_error_0() {
+        let fp = write_temp_file("load_data_error_0", "N0\tN1\tClass\n0\t2\t1\n1\tx\t0\n");
+
+        match load_data(&fp) {
+            Err(AntsnapError::Data { line, column, .. }) => {
+                assert_eq!(line, 3);
+                assert_eq!(column, Some(2));
+            }
+            _ => panic!("expected a data error"),
+        }
+
+        let a: Matrix = (vec![1.0, 2.0], 1);
+        let b: Matrix = (vec![1.0], 1);
+        assert!(append_columns(&a, &b).is_err());
+    }
+
+    #[test]
+    fn validate_params_0() {
+        let mut params = get_default_config().algo;
+        assert!(params.validate().is_ok());
+
+        params.t_0 = Some(5.0);
+        assert!(params.validate().is_err());
+
+        params.t_0 = Some(0.9);
+        params.checkpoint_every = Some(5);
+        assert!(params.validate().is_err());
+
+        // epis_dim is checked against the number of SNPs in the data
+        let (x, y, header) = toy_dataset();
+        params.checkpoint_every = None;
+        params.epis_dim = Some(7);
+        match AcoRunner::new(x, y, header, &params.resolve()) {
+            Err(AntsnapError::InvalidParam { name, .. }) => assert_eq!(name, "epis_dim"),
+            _ => panic!("expected epis_dim to be rejected"),
+        }
+    }
+
+    #[test]
+    fn load_config_unknown_key_0() {
+        let fp = write_temp_file(
+            "load_config_unknown_key_0",
+            "[algo]\nnum_ant = 10\ndata_fp = 'data'\n",
+        );
+
+        match load_config(std::path::Path::new(&fp)) {
+            Err(AntsnapError::Config { msg, .. }) => assert!(msg.contains("num_ant")),
+            _ => panic!("expected the misspelled key to be rejected"),
+        }
+    }
+
+    #[test]
+    fn build_contingency_table_0() {
+        // two SNPs, so 9 genotype columns
+        let x: Genotypes = (vec![0, 0, 2, 1, 2, 1], 2);
+        let y: Matrix = (vec![0.0, 1.0, 1.0], 1);
+
+        let table = build_contingency_table(&x, &y);
+
+        assert_eq!(table.1, 9);
+        assert_eq!(table.0.get(0), Some(&1.0));
+        assert_eq!(table.0.get(9 + 7), Some(&2.0));
+        assert_eq!(table.0.iter().sum::<f64>(), 3.0);
+    }
+
+    #[test]
+    fn resolve_params_0() {
+        // the default config and an empty one resolve to the same values
+        let from_defaults = get_default_config().algo.resolve();
+        let from_empty = AlgoParams::default().resolve();
+
+        assert_eq!(from_defaults, from_empty);
+        assert_eq!(from_empty, ResolvedParams::default());
+        assert_eq!(from_empty.data_fp, None);
+
+        let mut params = AlgoParams::default();
+        params.num_ants = Some(12);
+        assert_eq!(params.resolve().num_ants, 12);
+    }
+
+    #[test]
+    fn next_combination_0() {
+        let mut comb: Vec<usize> = vec![0, 1, 2];
+        let mut count: usize = 1;
+        while next_combination(&mut comb, 5) {
+            count += 1;
+        }
+        assert_eq!(count, 10);
+        assert_eq!(comb, vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn exhaustive_search_0() {
+        let (x, y, _) = toy_dataset();
+        let top = exhaustive_search(&x, &y, 3, 5);
+
+        assert_eq!(top.len(), 5);
+        assert_eq!(top[0].0, vec![1, 3, 4]);
+        assert_eq!(top[0].1, path_x2(&[1, 3, 4], &x, &y));
+        assert!(top.windows(2).all(|w| w[0].1 >= w[1].1));
+    }
+
+    #[test]
+    fn resolve_snp_names_0() {
+        let header: Vec<String> = vec!["N0".to_owned(), "N1".to_owned(), "M0P0".to_owned()];
+        let names: Vec<String> = vec!["M0P0".to_owned(), "N0".to_owned()];
+        assert_eq!(resolve_snp_names(&header, &names).unwrap(), vec![2, 0]);
+
+        let unknown: Vec<String> = vec!["N7".to_owned()];
+        assert!(resolve_snp_names(&header, &unknown).is_err());
+        assert_eq!(genotype_label(5, 2), "1/2");
+    }
+
+    #[test]
+    fn chi_square_sf_0() {
+        assert!((ln_gamma(5.0) - 24.0_f64.ln()).abs() < 1e-10);
+        assert!((chi_square_sf(3.841458820694124, 1) - 0.05).abs() < 1e-9);
+        assert!((chi_square_sf(18.307038053275146, 10) - 0.05).abs() < 1e-9);
+        assert!((chi_square_sf(2.0, 2) - (-1.0_f64).exp()).abs() < 1e-12);
+        assert_eq!(chi_square_sf(0.0, 4), 1.0);
+        assert_eq!(chi_square_sf(5.0, 0), 1.0);
+    }
+
+    #[test]
+    fn inspect_snp_set_0() {
+        let (x, y, header) = toy_dataset();
+        let names: Vec<String> = vec!["N4".to_owned(), "N1".to_owned(), "N3".to_owned()];
+        let report = inspect_snp_set(&x, &y, &header, &names, &AssociationTest::default()).unwrap();
+
+        assert_eq!(report.path, vec![4, 1, 3]);
+        assert_eq!(report.cells.len(), 27);
+        assert!((report.chi_square - path_x2(&[1, 3, 4], &x, &y)).abs() < 1e-9);
+        assert!(report.p_value < 1e-6);
+
+        let total: f64 = report.cells.iter().map(|c| c.controls + c.cases).sum();
+        assert_eq!(total, 120.0);
+
+        // the phenotype is the parity of the genotypes
+        for cell in report.cells.iter() {
+            if let Some(penetrance) = cell.penetrance() {
+                let odd: bool = cell.genotypes.iter().sum::<usize>() % 2 == 1;
+                assert_eq!(penetrance, if odd { 1.0 } else { 0.0 });
+            }
+        }
+
+        let repeated: Vec<String> = vec!["N1".to_owned(), "N1".to_owned()];
+        assert!(inspect_snp_set(&x, &y, &header, &repeated, &AssociationTest::default()).is_err());
+
+        // a seed alone doesn't change the test, so it isn't shown
+        let seeded = AssociationTest {
+            seed: Some(4),
+            ..AssociationTest::default()
+        };
+        assert!(seeded.is_plain());
+        let report = inspect_snp_set(&x, &y, &header, &names, &seeded).unwrap();
+        assert!(!report.to_string().contains("Test:"));
+
+        let g = AssociationTest {
+            statistic: TestStatistic::GTest,
+            ..seeded
+        };
+        assert!(!g.is_plain());
+        let report = inspect_snp_set(&x, &y, &header, &names, &g).unwrap();
+        assert!(report.to_string().contains("Test: g_test"));
+    }
+
+    #[test]
+    fn simulate_0() {
+        let params = SimParams {
+            num_snps: 12,
+            num_samples: 800,
+            epis_dim: 2,
+            heritability: 0.4,
+            maf_min: 0.3,
+            seed: Some(11),
+            ..SimParams::default()
+        };
+
+        let data = simulate(&params).unwrap();
+        assert_eq!(data.x.1, 12);
+        assert_eq!(data.y.0.len(), 800);
+        assert_eq!(data.y.0.iter().sum::<f64>(), 400.0);
+        assert_eq!(data.header[10], "M0P0");
+        assert_eq!(data.header[12], "Class");
+        assert_eq!(data.x.0, simulate(&params).unwrap().x.0);
+
+        let top = exhaustive_search(&data.x, &data.y, 2, 1);
+        assert_eq!(top[0].0, vec![10, 11]);
+    }
+
+    #[test]
+    fn random_penetrance_model_0() {
+        let mafs: Vec<f64> = vec![0.2, 0.35, 0.5];
+        let mut rng = rand_pcg::Pcg32::new(5, 7);
+        let model = random_penetrance_model(&mafs, 0.05, true, &mut rng).unwrap();
+
+        let freqs: Vec<Vec<f64>> = mafs
+            .iter()
+            .map(|p| vec![(1.0 - p) * (1.0 - p), 2.0 * p * (1.0 - p), p * p])
+            .collect();
+
+        // purely epistatic, every single SNP genotype has the prevalence
+        // as its marginal penetrance
+        for snp in 0..3 {
+            for genotype in 0..3 {
+                let mut sum: f64 = 0.0;
+                let mut total: f64 = 0.0;
+                for (cell, penetrance) in model.penetrances.iter().enumerate() {
+                    let gts: Vec<usize> = (0..3).map(|s| cell / 3_usize.pow(2 - s) % 3).collect();
+                    if gts[snp] != genotype {
+                        continue;
+                    }
+                    let weight: f64 = (0..3).map(|s| freqs[s][gts[s]]).product();
+                    sum += penetrance * weight;
+                    total += weight;
+                }
+                assert!((sum / total - model.prevalence).abs() < 1e-9);
+            }
+        }
+
+        assert!(model.penetrances.iter().all(|p| (0.0..=1.0).contains(p)));
+    }
+
+    #[test]
+    fn benchmark_0() {
+        let sim_params = SimParams {
+            num_snps: 20,
+            num_samples: 600,
+            epis_dim: 2,
+            heritability: 0.3,
+            maf_min: 0.3,
+            seed: Some(2),
+            ..SimParams::default()
+        };
+        let data = BenchmarkData::Simulated {
+            params: sim_params,
+            replicates: 2,
+        };
+
+        let mut params = ResolvedParams::default();
+        params.epis_dim = 2;
+        params.num_ants = 200;
+        params.max_iters = 4;
+        params.seed = Some(1);
+
+        let mut n_seen: usize = 0;
+        let results = run_benchmark(&data, &params, |_| n_seen += 1).unwrap();
+        assert_eq!(n_seen, 2);
+        assert_eq!(results[1].name, "sim_1");
+        assert!(results.iter().all(|r| r.true_rank.is_some()));
+
+        let mut ranked = results.to_owned();
+        ranked[0].true_rank = Some(4);
+        ranked[1].true_rank = None;
+        let summary = summarize(&ranked, 5);
+        assert_eq!(summary.top_1_power, 0.0);
+        assert_eq!(summary.top_n_power, 0.5);
+        assert_eq!(summary.mean_rank, Some(4.0));
+        assert_eq!(summary.found, 0.5);
+    }
+
+    #[test]
+    fn parse_sweep_values_0() {
+        assert_eq!(
+            parse_sweep_values("t_0", "0.5, 0.9").unwrap(),
+            vec![0.5, 0.9]
+        );
+        assert_eq!(
+            parse_sweep_values("lambda", "1:2:3").unwrap(),
+            vec![1.0, 1.5, 2.0]
+        );
+        assert_eq!(
+            parse_sweep_counts("num_ants", "10:20:4").unwrap(),
+            vec![10, 13, 17, 20]
+        );
+        assert!(parse_sweep_values("t_0", "0.5:1").is_err());
+        assert!(parse_sweep_values("t_0", "0.5:1:1").is_err());
+        assert!(parse_sweep_values("t_0", "a").is_err());
+
+        let space = SweepSpace {
+            t_0: vec![0.5, 0.9],
+            num_ants: vec![10, 20, 30],
+            ..SweepSpace::default()
+        };
+        let grid = space.grid(&ResolvedParams::default());
+        assert_eq!(grid.len(), 6);
+        assert!(grid.iter().all(|p| p.lambda == 2.0));
+    }
+
+    #[test]
+    fn run_sweep_0() {
+        let data = BenchmarkData::Simulated {
+            params: SimParams {
+                num_snps: 15,
+                num_samples: 400,
+                epis_dim: 2,
+                heritability: 0.3,
+                maf_min: 0.3,
+                seed: Some(4),
+                ..SimParams::default()
+            },
+            replicates: 4,
+        };
+
+        let mut base = ResolvedParams::default();
+        base.epis_dim = 2;
+        base.max_iters = 2;
+        base.seed = Some(1);
+
+        let space = SweepSpace {
+            num_ants: vec![2, 5, 150, 200],
+            ..SweepSpace::default()
+        };
+
+        let mut n_runs: usize = 0;
+        let sweep = run_sweep(
+            &data,
+            &base,
+            &space,
+            SearchStrategy::Halving(2),
+            10,
+            None,
+            |_, _| n_runs += 1,
+        )
+        .unwrap();
+
+        // 4 settings on 1 replicate, 2 on 2 and 1 on 4
+        assert_eq!(n_runs, 4 + 2 + 2);
+        assert_eq!(sweep[0].results.len(), 4);
+        assert!(sweep[0].params.num_ants >= 150);
+        assert_eq!(sweep[3].results.len(), 1);
+    }
+
+    #[test]
+    fn missing_genotypes_0() {
+        let fp = write_temp_file(
+            "missing.txt",
+            "N0\tN1\tN2\tClass\n0\tNA\t2\t1\n1\t-9\t0\t0\n./.\t1\t2\t1\nNA\t0\t.\t0\n",
+        );
+        let (x, y, _) = load_data(&fp).unwrap();
+
+        for idx in [1, 4, 6, 9, 11].iter() {
+            assert_eq!(x.0[*idx], MISSING_CODE);
+        }
+        assert_eq!(y.0, vec![1.0, 0.0, 1.0, 0.0]);
+
+        let table = build_contingency_table(&column_subset(&x, &[0, 2]), &y);
+        assert_eq!(table.0.iter().sum::<f64>(), 2.0);
+        assert_eq!(count_complete(&x, &[0, 2]), 2);
+        assert_eq!(count_complete(&x, &[1]), 2);
+        assert_eq!(count_complete(&x, &[0, 1, 2]), 0);
+
+        // no sample is complete for all three, the table is empty
+        let score: f64 = path_x2(&[0, 1, 2], &x, &y);
+        assert!(score.is_finite());
+        assert_eq!(score, 0.0);
+        let empty = build_contingency_table(&column_subset(&x, &[0, 1, 2]), &y);
+        assert_eq!(g_test(&empty), 0.0);
+
+        // a NaN score sorts after every real one
+        let mut scores: Vec<f64> = vec![1.0, f64::NAN, 3.0, 2.0];
+        scores.sort_by(|a, b| best_first(*a, *b));
+        assert_eq!(scores[..3], [3.0, 2.0, 1.0]);
+        assert!(scores[3].is_nan());
+        assert_eq!(benjamini_hochberg(&[0.01, f64::NAN]).len(), 2);
+        assert_eq!(get_median(&[3.0, f64::NAN, 1.0]), 3.0);
+
+        let mut pheromones: Matrix = init_pheromones(3);
+        pheromones.0[1] = f64::NAN;
+        assert_eq!(snp_importance(&pheromones, &[0, 0, 0]).len(), 3);
+
+        let (x_complete, y_complete) = complete_rows(&column_subset(&x, &[0, 2]), &y);
+        assert_eq!(x_complete.0, vec![0, 2, 1, 0]);
+        assert_eq!(y_complete.0, vec![1.0, 0.0]);
+
+        let bad_class = write_temp_file("missing_class.txt", "N0\tClass\n0\tNA\n");
+        assert!(load_data(&bad_class).is_err());
+    }
+
+    #[test]
+    fn hwe_exact_p_0() {
+        // counts exactly at HWE proportions
+        assert!(hwe_exact_p(50, 25, 25) > 0.5);
+        assert!(hwe_exact_p(0, 50, 50) < 1e-20);
+        assert_eq!(hwe_exact_p(0, 100, 0), 1.0);
+        // 1 rare allele can only be in a heterozygote
+        assert!((hwe_exact_p(1, 99, 0) - 1.0).abs() < 1e-12);
+        // summing the exact genotype probabilities directly gives
+        // 3.7009462e-7 for 21 hets, 57 and 22 homozygotes
+        assert!((hwe_exact_p(21, 57, 22) / 3.7009462135649153e-7 - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn run_qc_0() {
+        let n_rows: usize = 100;
+        let mut x: Genotypes = (Vec::new(), 4);
+        let mut y: Matrix = (Vec::new(), 1);
+
+        for row in 0..n_rows {
+            // N0 is fine, N1 is monomorphic, N2 is mostly missing and N3
+            // has no heterozygotes
+            let n0: u8 = [0, 1, 1, 2][row % 4];
+            let n2: u8 = if row % 3 == 0 { n0 } else { MISSING_CODE };
+            let n3: u8 = if row % 2 == 1 { 2 } else { 0 };
+            x.0.extend(vec![n0, 0, n2, n3]);
+            y.0.push((row % 5 == 1) as usize as f64);
+        }
+
+        let header: Vec<String> = vec!["N0", "N1", "N2", "N3", "Class"]
+            .into_iter()
+            .map(|h| h.to_owned())
+            .collect();
+
+        let mut params = ResolvedParams::default();
+        let (_, _, qc) = apply_qc(x.to_owned(), &y, header.to_owned(), &params).unwrap();
+        assert_eq!(qc.kept, vec![0, 1, 2, 3]);
+
+        params.min_call_rate = Some(0.9);
+        params.min_maf = Some(0.01);
+        params.min_hwe_p = Some(1e-6);
+        let (x_kept, header_kept, qc) = apply_qc(x, &y, header, &params).unwrap();
+
+        assert_eq!(qc.kept, vec![0]);
+        assert_eq!(qc.removed[0], (1, QcReason::Maf(0.0)));
+        assert!(matches!(qc.removed[1], (2, QcReason::CallRate(_))));
+        assert!(matches!(qc.removed[2], (3, QcReason::Hwe(_))));
+        assert_eq!(x_kept.1, 1);
+        assert_eq!(header_kept, vec!["N0".to_owned(), "Class".to_owned()]);
+    }
+
+    #[test]
+    fn ld_prune_0() {
+        // N1 copies N0, N3 copies N2 but is on another chromosome than
+        // N2 is
+        let mut x: Genotypes = (Vec::new(), 4);
+        for row in 0..60 {
+            let a: u8 = (row % 3) as u8;
+            let b: u8 = ((row / 3) % 3) as u8;
+            x.0.extend(vec![a, a, b, b]);
+        }
+
+        assert!((r_squared(&x, 0, 1) - 1.0).abs() < 1e-12);
+        assert!(r_squared(&x, 0, 2) < 0.1);
+
+        let pos = |chrom: &str, bp: u64| SnpPosition {
+            chrom: chrom.to_owned(),
+            bp,
+        };
+        let positions: Vec<SnpPosition> =
+            vec![pos("1", 100), pos("1", 200), pos("1", 300), pos("2", 300)];
+
+        let removed: Vec<(usize, f64)> = ld_prune(&x, &[0, 1, 2, 3], &positions, 1000, 0.8);
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].0, 1);
+
+        // N1 is out of the window
+        let removed: Vec<(usize, f64)> = ld_prune(&x, &[0, 1, 2, 3], &positions, 50, 0.8);
+        assert!(removed.is_empty());
+    }
+
+    #[test]
+    fn ld_partners_0() {
+        use rand::{Rng, SeedableRng};
+
+        let mut x: Genotypes = (Vec::new(), 5);
+        for row in 0..60 {
+            let a: u8 = (row % 3) as u8;
+            let b: u8 = ((row / 3) % 3) as u8;
+            let c: u8 = ((row / 9) % 3) as u8;
+            x.0.extend(vec![a, a, b, c, (row % 2) as u8]);
+        }
+
+        let linked: Vec<Vec<usize>> = ld_partners(&x, None, 0, 0.8);
+        assert_eq!(linked[0], vec![1]);
+        assert_eq!(linked[1], vec![0]);
+        assert!(linked[2].is_empty());
+
+        // each pair is computed once and mirrored, in order
+        let mut x_3: Genotypes = (Vec::new(), 4);
+        for row in 0..60 {
+            let a: u8 = (row % 3) as u8;
+            let b: u8 = ((row / 3) % 3) as u8;
+            x_3.0.extend(vec![a, b, a, a]);
+        }
+        let linked_3: Vec<Vec<usize>> = ld_partners(&x_3, None, 0, 0.8);
+        assert_eq!(linked_3, vec![vec![2, 3], vec![], vec![0, 3], vec![0, 2]]);
+
+        let pheromones: Matrix = init_pheromones(5);
+        let mut rng = rand::rngs::StdRng::seed_from_u64(11);
+        for _ in 0..200 {
+            let mut path: Vec<usize> = vec![rng.gen_range(0, 5)];
+            expand_path_excluding(&mut path, &pheromones, 3, 0.5, &linked, &mut rng);
+
+            assert_eq!(path.len(), 3);
+            assert!(!(path.contains(&0) && path.contains(&1)));
+        }
+    }
+
+    #[test]
+    fn load_positions_0() {
+        let fp = write_temp_file(
+            "positions.bim",
+            "1\tN1\t0\t1500\tA\tG\n1\tN0\t0\t1200\tC\tT\n2\tN2\t0\t80\tA\tC\n",
+        );
+        let header: Vec<String> = vec!["N0", "N1", "Class"]
+            .into_iter()
+            .map(|h| h.to_owned())
+            .collect();
+
+        let positions: Vec<SnpPosition> = load_positions(&fp, &header, 2).unwrap();
+        assert_eq!(positions[0].bp, 1200);
+        assert_eq!(positions[1].bp, 1500);
+
+        let header: Vec<String> = vec!["N0".to_owned(), "N7".to_owned()];
+        assert!(load_positions(&fp, &header, 2).is_err());
+    }
+
+    #[test]
+    fn store_0() {
+        let fp = write_temp_file(
+            "store_0.txt",
+            "N0\tN1\tN2\tClass\n0\tNA\t2\t1\n1\t2\t0\t0\n2\t1\t.\t1\n",
+        );
+        let cache_fp = std::env::temp_dir()
+            .join("antsnap_test_store_0.antsnap")
+            .to_str()
+            .unwrap()
+            .to_owned();
+        let _ = std::fs::remove_file(&cache_fp);
+
+        let mut reports: Vec<LoadProgress> = Vec::new();
+        let (store, header) =
+            stream_data(&fp, &LoadOptions::default(), |p| reports.push(*p)).unwrap();
+        assert_eq!(
+            store.codes,
+            vec![0, MISSING_CODE, 2, 1, 2, 0, 2, 1, MISSING_CODE]
+        );
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].rows, 3);
+        assert_eq!(reports[0].fraction(), 1.0);
+
+        // the first load writes the cache, the second reads it
+        let from_text = load_data_cached(&fp, &cache_fp, &LoadOptions::default(), |_| {}).unwrap();
+        assert!(is_binary_dataset(&cache_fp));
+        let from_cache = load_data_cached(&fp, &cache_fp, &LoadOptions::default(), |_| {}).unwrap();
+        assert_eq!(from_text, load_data(&fp).unwrap());
+        assert_eq!(from_cache.0 .0.len(), from_text.0 .0.len());
+        assert_eq!(from_cache.0 .0[1], MISSING_CODE);
+        assert_eq!(from_cache.1, from_text.1);
+        assert_eq!(from_cache.2, header);
+
+        let mapped = MappedStore::open(&cache_fp).unwrap();
+        assert_eq!(mapped.row(1), &[1, 2, 0]);
+        assert_eq!(mapped.phenotype(2), 1.0);
+        assert_eq!(mapped.sample_ids, vec!["sample1", "sample2", "sample3"]);
+        // binary datasets are detected by load_data too
+        assert_eq!(load_data(&cache_fp).unwrap(), from_cache);
+
+        // a changed source invalidates the cache
+        std::fs::write(&fp, "N0\tN1\tN2\tClass\n0\t1\t2\t1\n").unwrap();
+        let (x, _, _) = load_data_cached(&fp, &cache_fp, &LoadOptions::default(), |_| {}).unwrap();
+        assert_eq!(x.0, vec![0, 1, 2]);
+        assert_eq!(MappedStore::open(&cache_fp).unwrap().num_samples, 1);
+
+        let mut bytes = std::fs::read(&cache_fp).unwrap();
+        let last: usize = bytes.len() - 9;
+        bytes[last] = 1;
+        std::fs::write(&cache_fp, &bytes).unwrap();
+        assert!(matches!(
+            load_data(&cache_fp),
+            Err(AntsnapError::Cache { .. })
+        ));
+
+        std::fs::write(&cache_fp, &bytes[..bytes.len() - 1]).unwrap();
+        assert!(matches!(
+            MappedStore::open(&cache_fp),
+            Err(AntsnapError::Cache { .. })
+        ));
+
+        let bad = write_temp_file("store_bad.txt", "N0\tClass\n0.5\t1\n");
+        assert!(load_data(&bad).is_err());
+    }
+
+    #[test]
+    fn load_options_0() {
+        let csv = write_temp_file(
+            "options.csv",
+            "IID,rs123,rs456,status,rs789\nA1,0,2,1,NA\nA2,1,1,0,2\n",
+        );
+        let options = LoadOptions {
+            delimiter: Delimiter::Comma,
+            has_header: None,
+            phenotype_col: Some("status".to_owned()),
+            sample_id_cols: vec!["IID".to_owned()],
+        };
+
+        let (store, header) = stream_data(&csv, &options, |_| {}).unwrap();
+        assert_eq!(header, vec!["rs123", "rs456", "rs789", "status"]);
+        assert_eq!(store.codes, vec![0, 2, MISSING_CODE, 1, 1, 2]);
+        assert_eq!(store.phenotype, vec![1.0, 0.0]);
+        assert_eq!(store.sample_ids, vec!["A1", "A2"]);
+
+        let mut bad_options = options.to_owned();
+        bad_options.phenotype_col = Some("case".to_owned());
+        assert!(matches!(
+            stream_data(&csv, &bad_options, |_| {}),
+            Err(AntsnapError::InvalidParam { .. })
+        ));
+
+        // a header that doesn't start with N is still detected
+        let rs = write_temp_file("options_rs.txt", "rs1 rs2 Class\n0 1 1\n2 1 0\n");
+        let (x, _, header) = load_data(&rs).unwrap();
+        assert_eq!(header, vec!["rs1", "rs2", "Class"]);
+        assert_eq!(x.0.len(), 4);
+
+        // no header, with IDs in the first column
+        let headerless = write_temp_file("options_headerless.txt", "S1\t0\t1\t1\nS2\t2\t0\t0\n");
+        let options = LoadOptions {
+            delimiter: Delimiter::Tab,
+            sample_id_cols: vec!["1".to_owned()],
+            ..LoadOptions::default()
+        };
+        let (store, header) = stream_data(&headerless, &options, |_| {}).unwrap();
+        assert_eq!(header, vec!["snp1", "snp2", "Class"]);
+        assert_eq!(store.codes, vec![0, 1, 2, 0]);
+        assert_eq!(store.sample_ids, vec!["S1", "S2"]);
+
+        // a phenotype coded 1 and 2 is rejected, not read as controls
+        let coded = write_temp_file("options_coded.csv", "IID,rs123,status\nA1,0,1\nA2,1,2\n");
+        let options = LoadOptions {
+            delimiter: Delimiter::Comma,
+            has_header: None,
+            phenotype_col: Some("status".to_owned()),
+            sample_id_cols: vec!["IID".to_owned()],
+        };
+        match stream_data(&coded, &options, |_| {}) {
+            Err(AntsnapError::Data {
+                line, column, msg, ..
+            }) => {
+                assert_eq!(line, 3);
+                assert_eq!(column, Some(3));
+                assert!(msg.contains("'2'"));
+            }
+            _ => panic!("expected the phenotype to be rejected"),
+        }
+    }
+
+    #[test]
+    fn holdout_0() {
+        let (x, y, _) = toy_dataset();
+        let n_cases: usize = y.0.iter().filter(|v| **v != 0.0).count();
+
+        let split = stratified_split(&y, 0.25, Some(5)).unwrap();
+        assert_eq!(split, stratified_split(&y, 0.25, Some(5)).unwrap());
+        assert_eq!(split.discovery.len() + split.replication.len(), y.0.len());
+        assert!(split
+            .replication
+            .iter()
+            .all(|row| split.discovery.binary_search(row).is_err()));
+
+        // each class is held out in proportion
+        let held_cases: usize = split
+            .replication
+            .iter()
+            .filter(|row| y.0[**row] != 0.0)
+            .count();
+        assert_eq!(held_cases, (n_cases as f64 * 0.25).round() as usize);
+
+        let (discovery, replication) = split.apply(&x, &y);
+        assert_eq!(discovery.0 .0.len(), split.discovery.len() * x.1);
+        assert_eq!(replication.1 .0.len(), split.replication.len());
+
+        let solution = Solution {
+            path: vec![1, 3, 4],
+            snps: vec!["N1".to_owned(), "N3".to_owned(), "N4".to_owned()],
+            score: 0.0,
+            n_samples: 0,
+            times_found: 1,
+        };
+        let reps = replicate_solutions(
+            &[solution],
+            (&discovery.0, &discovery.1),
+            (&replication.0, &replication.1),
+            &AssociationTest::default(),
+        );
+        assert_eq!(reps[0].replication.n_samples, split.replication.len());
+        assert!(reps[0].discovery.p_value < 1e-6);
+        // the parity of [1, 3, 4] still separates the held out samples
+        // perfectly, though there are too few of them for a small p-value
+        let held: &SetStats = &reps[0].replication;
+        assert!((held.statistic - held.n_samples as f64).abs() < 1e-9);
+        assert!(held.p_value < 0.05);
+
+        assert!(stratified_split(&y, 0.001, Some(5)).is_err());
+    }
+
+    #[test]
+    fn stability_0() {
+        use rand::SeedableRng;
+
+        let (x, y, header) = toy_dataset();
+        let mut params = ResolvedParams::default();
+        params.num_ants = 30;
+        params.max_iters = 5;
+        params.seed = Some(3);
+
+        // a bootstrap resample keeps the number of cases
+        let mut rng = rand_pcg::Pcg32::seed_from_u64(1);
+        let rows: Vec<usize> = bootstrap_rows(&y, &mut rng);
+        let n_cases = |rows: &[usize]| rows.iter().filter(|row| y.0[**row] != 0.0).count();
+        assert_eq!(rows.len(), y.0.len());
+        assert_eq!(
+            n_cases(&rows),
+            n_cases(&(0..y.0.len()).collect::<Vec<usize>>())
+        );
+
+        let result = run_stability(&x, &y, &header, &params, 4, 3).unwrap();
+        assert_eq!(
+            result,
+            run_stability(&x, &y, &header, &params, 4, 3).unwrap()
+        );
+
+        assert!(result.sets.iter().map(|s| s.count).sum::<usize>() <= 4 * 3);
+        assert!(result.sets.windows(2).all(|w| w[0].count >= w[1].count));
+        assert!(result.snps.iter().all(|s| s.count <= 4));
+        assert!(result
+            .sets
+            .iter()
+            .all(|s| (s.frequency - s.count as f64 / 4.0).abs() < 1e-12));
+
+        assert!(run_stability(&x, &y, &header, &params, 0, 3).is_err());
+    }
+
+    #[test]
+    fn multiple_testing_0() {
+        assert_eq!(n_choose_k(30, 3), 4060.0);
+        assert_eq!(n_choose_k(5, 0), 1.0);
+        assert_eq!(n_choose_k(3, 4), 0.0);
+        assert!((n_choose_k(100_000, 2) - 4_999_950_000.0).abs() < 1e-3);
+
+        assert_eq!(bonferroni(0.001, 10.0), 0.01);
+        assert_eq!(bonferroni(0.2, 10.0), 1.0);
+
+        let q = benjamini_hochberg(&[0.01, 0.04, 0.03, 0.005]);
+        let expected: Vec<f64> = vec![0.02, 0.04, 0.04, 0.02];
+        assert!(q
+            .iter()
+            .zip(expected.iter())
+            .all(|(a, b)| (a - b).abs() < 1e-12));
+        assert!(benjamini_hochberg(&[]).is_empty());
+    }
+
+    #[test]
+    fn assess_solutions_0() {
+        let (x, y, header) = toy_dataset();
+        let mut params = ResolvedParams::default();
+        params.num_ants = 30;
+        params.max_iters = 5;
+        params.seed = Some(3);
+
+        let result = AcoRunner::new(x.clone(), y.clone(), header, &params)
+            .unwrap()
+            .run()
+            .unwrap();
+        assert!(result.evaluated.windows(2).all(|w| w[0] < w[1]));
+        assert!(result
+            .solutions
+            .iter()
+            .all(|s| result.evaluated.binary_search(&s.path).is_ok()));
+
+        let report = assess_solutions(&result.solutions, &result.evaluated, &x, &y, 0.05, &params);
+        assert_eq!(report.search_space, 20.0);
+        assert_eq!(report.evaluated, result.evaluated.len());
+        assert_eq!(report.solutions.len(), result.solutions.len());
+
+        // the parity SNPs are found and survive either correction
+        let best = &report.solutions[0];
+        assert_eq!(result.solutions[0].path, vec![1, 3, 4]);
+        assert!(best.significant);
+        assert!(best.stats.p_value <= best.q_value && best.q_value <= best.bonferroni);
+
+        for sig in report.solutions.iter() {
+            assert_eq!(sig.significant, sig.bonferroni < 0.05);
+        }
+
+        // Monte Carlo p-values are only computed for the solutions, and
+        // each solution is ranked by its own for its q-value
+        params.monte_carlo_iters = Some(200);
+        let mc_report =
+            assess_solutions(&result.solutions, &result.evaluated, &x, &y, 0.05, &params);
+        for sig in mc_report.solutions.iter() {
+            assert!(sig.stats.p_value >= 1.0 / 201.0);
+            assert!(sig.q_value >= sig.stats.p_value);
+        }
+    }
+
+    #[test]
+    fn association_test_0() {
+        // one sparse genotype column among three
+        let table: Matrix = (vec![30.0, 20.0, 1.0, 10.0, 25.0, 0.0], 3);
+
+        let dropped: Matrix = collapse_sparse_cells(&table, 5.0, SparseCells::Drop);
+        assert_eq!(dropped, (vec![30.0, 20.0, 10.0, 25.0], 2));
+
+        // the pooled column is still sparse, so it joins the smaller one
+        let merged: Matrix = collapse_sparse_cells(&table, 5.0, SparseCells::Merge);
+        assert_eq!(merged, (vec![31.0, 20.0, 10.0, 25.0], 2));
+        assert_eq!(collapse_sparse_cells(&table, 5.0, SparseCells::Keep), table);
+
+        // G is 2 * sum(O * ln(O / E)) over the nonempty cells
+        let t: Matrix = (vec![10.0, 20.0, 20.0, 10.0], 2);
+        let g: f64 = 4.0 * (10.0 * (10.0_f64 / 15.0).ln() + 20.0 * (20.0_f64 / 15.0).ln());
+        assert!((g_test(&t) - g).abs() < 1e-9);
+
+        let mut test = AssociationTest {
+            sparse_cells: SparseCells::Drop,
+            ..AssociationTest::default()
+        };
+        let stats: SetStats = test.test_table(&table, &[0]);
+        assert_eq!(stats.df, 1);
+        assert_eq!(stats.n_samples, 85);
+
+        // every cell of a 3 SNP table over 120 samples is sparse
+        let (x, y, _) = toy_dataset();
+        let empty: SetStats = path_stats(&[1, 3, 4], &x, &y, &test);
+        assert_eq!((empty.n_samples, empty.p_value), (0, 1.0));
+
+        // Monte Carlo p-values are seeded per SNP set and can't be 0
+        test.sparse_cells = SparseCells::Keep;
+        test.monte_carlo_iters = Some(200);
+        test.seed = Some(9);
+        let strong: SetStats = path_stats(&[1, 3, 4], &x, &y, &test);
+        assert_eq!(strong, path_stats(&[1, 3, 4], &x, &y, &test));
+        assert!((strong.p_value - 1.0 / 201.0).abs() < 1e-12);
+
+        let weak: SetStats = path_stats(&[0], &x, &y, &test);
+        assert!(weak.p_value > 0.01);
+    }
+
+    #[test]
+    fn time_limit_0() {
+        // enough SNPs that each ant is slow to build its path, and more ants
+        // than can be built before the deadline, so the first iteration
+        // crosses it
+        let n_snps: usize = 60;
+        let n_rows: usize = 100;
+        let x: Genotypes = (
+            (0..n_rows * n_snps)
+                .map(|i| ((i * 7 + i / 13) % 3) as u8)
+                .collect(),
+            n_snps,
+        );
+        let y: Matrix = ((0..n_rows).map(|row| (row % 2) as f64).collect(), 1);
+        let header: Vec<String> = (0..n_snps).map(|i| format!("N{}", i)).collect();
+
+        let mut params = ResolvedParams::default();
+        params.num_ants = 200_000;
+        params.max_iters = 5;
+        params.max_time_secs = Some(0.5);
+        params.seed = Some(3);
+
+        let result: AcoResult = AcoRunner::new(x, y, header, &params)
+            .unwrap()
+            .run()
+            .unwrap();
+
+        assert_eq!(
+            result.stop_reason,
+            StopReason::TimeLimit(std::time::Duration::from_secs_f64(0.5))
+        );
+        assert_eq!(result.iterations.len(), 1);
+
+        // only the ants built before the deadline count, and the best of
+        // them are kept
+        let built: usize = result.visit_counts.iter().sum::<usize>() / params.epis_dim;
+        assert!(built > 0 && built < params.num_ants);
+        assert!(!result.solutions.is_empty());
+    }
+
+    #[test]
+    fn elite_reinforcement_0() {
+        assert_eq!(elite_count(19), 0);
+        assert_eq!(elite_count(200), 10);
+        assert_eq!(elite_count(1000), 50);
+
+        // enough SNPs that few of the elite paths' edges are also on the
+        // other paths, which evaporate them again
+        let n_snps: usize = 60;
+        let n_rows: usize = 100;
+        let x: Genotypes = (
+            (0..n_rows * n_snps)
+                .map(|i| ((i * 7 + i / 13) % 3) as u8)
+                .collect(),
+            n_snps,
+        );
+        let y: Matrix = ((0..n_rows).map(|row| (row % 2) as f64).collect(), 1);
+        let header: Vec<String> = (0..n_snps).map(|i| format!("N{}", i)).collect();
+
+        let mut params = ResolvedParams::default();
+        params.num_ants = 200;
+        params.max_iters = 1;
+        params.seed = Some(11);
+
+        let result: AcoResult = AcoRunner::new(x, y, header, &params)
+            .unwrap()
+            .run()
+            .unwrap();
+
+        assert_eq!(result.iterations[0].elite_paths, 10);
+        // pheromones start at 1.0 and evaporation alone only lowers them,
+        // with lambda above 1.0 reinforced edges rise above it
+        assert!(result.pheromones.0.iter().any(|val| *val > 1.0));
+    }
+
+    #[test]
+    fn checkpoint_visit_counts_0() {
+        let (x, y, header) = toy_dataset();
+        let fp: String = write_temp_file("checkpoint_visit_counts_0", "");
+
+        let mut params = ResolvedParams::default();
+        params.num_ants = 30;
+        params.max_iters = 2;
+        params.checkpoint_every = 2;
+        params.checkpoint_fp = Some(fp.to_owned());
+        params.seed = Some(5);
+
+        AcoRunner::new(x.clone(), y.clone(), header.clone(), &params)
+            .unwrap()
+            .run()
+            .unwrap();
+
+        // a checkpoint written before visit counts were saved
+        let mut json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&fp).unwrap()).unwrap();
+        json.as_object_mut().unwrap().remove("visit_counts");
+        std::fs::write(&fp, json.to_string()).unwrap();
+
+        let checkpoint = load_checkpoint(&fp).unwrap();
+        assert!(checkpoint.visit_counts.is_empty());
+
+        params.max_iters = 3;
+        let result: AcoResult = AcoRunner::new(x, y, header, &params)
+            .unwrap()
+            .run_from(Some(checkpoint))
+            .unwrap();
+
+        // only the resumed iteration is counted
+        assert_eq!(result.visit_counts.len(), 6);
+        assert_eq!(result.visit_counts.iter().sum::<usize>(), 30 * 3);
+    }
+
+    #[test]
+    fn trace_0() {
+        let (x, y, header) = toy_dataset();
+        let mut params = ResolvedParams {
+            num_ants: 30,
+            max_iters: 4,
+            seed: Some(2),
+            ..ResolvedParams::default()
+        };
+
+        for ext in ["csv", "jsonl"].iter() {
+            let fp: String = write_temp_file(&format!("trace_0.{}", ext), "");
+            params.trace_fp = Some(fp.to_owned());
+
+            let result: AcoResult = AcoRunner::new(x.clone(), y.clone(), header.clone(), &params)
+                .unwrap()
+                .run()
+                .unwrap();
+            assert_eq!(result.iterations.len(), 4);
+
+            let contents: String = std::fs::read_to_string(&fp).unwrap();
+            let mut lines: Vec<&str> = contents.lines().collect();
+
+            if *ext == "csv" {
+                assert!(lines.remove(0).starts_with("iter,best_score,"));
+                let rows: Vec<String> = result.iterations.iter().map(to_csv_row).collect();
+                assert_eq!(lines, rows);
+            } else {
+                assert_eq!(lines.len(), result.iterations.len());
+                for (line, stats) in lines.iter().zip(result.iterations.iter()) {
+                    let record: serde_json::Value = serde_json::from_str(line).unwrap();
+                    assert_eq!(record["iter"], stats.iter);
+                    assert_eq!(record["best_score"], stats.best_score);
+                    assert_eq!(record["elite_paths"], stats.elite_paths);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn checkpoint_resume_0() {
+        let (x, y, header) = toy_dataset();
+        let trace_fp: String = write_temp_file("checkpoint_resume_0.csv", "");
+        let checkpoint_fp: String = write_temp_file("checkpoint_resume_0.json", "");
+
+        let mut params = ResolvedParams {
+            num_ants: 30,
+            max_iters: 6,
+            seed: Some(8),
+            trace_fp: Some(trace_fp.to_owned()),
+            ..ResolvedParams::default()
+        };
+        let runner = |params: &ResolvedParams, y: &Matrix| {
+            AcoRunner::new(x.clone(), y.clone(), header.clone(), params).unwrap()
+        };
+        // the trace without the timings, which differ between runs
+        let read_trace = || -> Vec<String> {
+            std::fs::read_to_string(&trace_fp)
+                .unwrap()
+                .lines()
+                .map(|line| {
+                    let fields: Vec<&str> = line.split(',').collect();
+                    [&fields[..9], &fields[10..]].concat().join(",")
+                })
+                .collect()
+        };
+
+        let uninterrupted: AcoResult = runner(&params, &y).run().unwrap();
+        let full_trace: Vec<String> = read_trace();
+        assert_eq!(full_trace.len(), 7);
+
+        // stops after iteration 4 with the last checkpoint after 3, so
+        // the trace has a row the resumed run writes again
+        params.max_iters = 4;
+        params.checkpoint_every = 3;
+        params.checkpoint_fp = Some(checkpoint_fp.to_owned());
+        runner(&params, &y).run().unwrap();
+        assert_eq!(read_trace().len(), 5);
+
+        params.max_iters = 6;
+        let resumed: AcoResult = runner(&params, &y)
+            .run_from(Some(load_checkpoint(&checkpoint_fp).unwrap()))
+            .unwrap();
+
+        assert_eq!(resumed.solutions, uninterrupted.solutions);
+        assert_eq!(resumed.pheromones, uninterrupted.pheromones);
+        assert_eq!(resumed.visit_counts, uninterrupted.visit_counts);
+        assert_eq!(read_trace(), full_trace);
+
+        // a different config or dataset is refused, and the trace is left
+        let refused = |params: &ResolvedParams, y: &Matrix| -> String {
+            match runner(params, y).run_from(Some(load_checkpoint(&checkpoint_fp).unwrap())) {
+                Err(AntsnapError::Checkpoint { msg, .. }) => msg,
+                _ => panic!("expected the checkpoint to be refused"),
+            }
+        };
+
+        let mut changed_params = params.to_owned();
+        changed_params.lambda = 3.0;
+        assert!(refused(&changed_params, &y).contains("config has changed"));
+
+        let mut changed_y: Matrix = y.to_owned();
+        changed_y.0[0] = 1.0 - changed_y.0[0];
+        assert!(refused(&params, &changed_y).contains("dataset has changed"));
+
+        assert_eq!(read_trace(), full_trace);
+    }
+
+    #[test]
+    fn config_hash_0() {
+        let (x, y, header) = toy_dataset();
+        let params = ResolvedParams::default();
+        let hash = |params: &ResolvedParams| -> u64 {
+            AcoRunner::new(x.clone(), y.clone(), header.clone(), params)
+                .unwrap()
+                .config_hash()
+        };
+        let base: u64 = hash(&params);
+
+        // run length and outputs can change on resume
+        let longer = ResolvedParams {
+            max_iters: params.max_iters * 2,
+            max_time_secs: Some(60.0),
+            trace_fp: Some("trace.csv".to_owned()),
+            ..params.to_owned()
+        };
+        assert_eq!(hash(&longer), base);
+
+        // anything that changes the search can't
+        let changes: Vec<ResolvedParams> = vec![
+            ResolvedParams {
+                path_max_r2: Some(0.5),
+                ..params.to_owned()
+            },
+            ResolvedParams {
+                path_max_r2: Some(0.5),
+                ld_window_bp: params.ld_window_bp + 1,
+                ..params.to_owned()
+            },
+            ResolvedParams {
+                min_maf: Some(0.01),
+                ..params.to_owned()
+            },
+            ResolvedParams {
+                sparse_cells: "merge".to_owned(),
+                ..params.to_owned()
+            },
+        ];
+        let hashes: Vec<u64> = changes.iter().map(hash).collect();
+
+        assert!(hashes.iter().all(|h| *h != base));
+        assert_ne!(hashes[0], hashes[1]);
+    }
 }
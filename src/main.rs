@@ -1,31 +1,1326 @@
 extern crate clap;
-use clap::{App, Arg};
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
 
 use antsnap::algo::*;
+use antsnap::benchmark::*;
+use antsnap::checkpoint::*;
 use antsnap::config::*;
+use antsnap::contingency::*;
+use antsnap::error::*;
+use antsnap::export::*;
+use antsnap::holdout::*;
+use antsnap::inspect::*;
+use antsnap::qc::*;
+use antsnap::runner::*;
+use antsnap::significance::*;
+use antsnap::simulate::*;
+use antsnap::stability::*;
+use antsnap::store::*;
+use antsnap::sweep::*;
+use antsnap::utils::*;
 
 use std::path::PathBuf;
+use std::process;
+use std::str::FromStr;
+
+type Element = f64;
+type Matrix = (Vec<Element>, usize);
+type Genotypes = (Vec<u8>, usize);
+
+// number of solutions printed in the final report
+const N_SOLUTIONS_TO_REPORT: usize = 30;
 
 fn main() {
-    let matches = App::new("antsnap")
+    if let Err(why) = run() {
+        eprintln!("antsnap: error: {}", why);
+        process::exit(1);
+    }
+}
+
+// command line overrides for AlgoParams as (arg name, config key,
+// help). each arg is --<arg name> and takes a value
+const PARAM_ARGS: &[(&str, &str, &str)] = &[
+    ("data", "data_fp", "Path to the dataset"),
+    (
+        "cache",
+        "cache_fp",
+        "Binary copy of the dataset, written on first use",
+    ),
+    (
+        "data-delimiter",
+        "data_delimiter",
+        "Field delimiter of the dataset: whitespace, comma, tab or space",
+    ),
+    (
+        "has-header",
+        "has_header",
+        "Whether the dataset has a header line, detected if not given",
+    ),
+    (
+        "phenotype-col",
+        "phenotype_col",
+        "Name or 1-based number of the phenotype column",
+    ),
+    (
+        "sample-id-cols",
+        "sample_id_cols",
+        "Comma separated names or numbers of sample ID columns",
+    ),
+    (
+        "init-pheromone-val",
+        "init_pheromone_val",
+        "Initial pheromone value",
+    ),
+    (
+        "t-0",
+        "t_0",
+        "Threshold for moving to an SNP by random chance",
+    ),
+    (
+        "evap-coeff",
+        "evap_coeff",
+        "Pheromone evaporation coefficient",
+    ),
+    ("epis-dim", "epis_dim", "Number of SNPs in an interaction"),
+    ("num-ants", "num_ants", "Number of ants per iteration"),
+    ("max-iters", "max_iters", "Maximum number of iterations"),
+    ("lambda", "lambda", "Pheromone boost for good solutions"),
+    (
+        "patience",
+        "patience",
+        "Stop after this many iterations without improvement",
+    ),
+    (
+        "min-entropy",
+        "min_entropy",
+        "Stop when pheromone entropy falls below this",
+    ),
+    (
+        "max-time-secs",
+        "max_time_secs",
+        "Wall-clock budget in seconds, checked before each ant",
+    ),
+    ("trace", "trace_fp", "Path for the per-iteration trace"),
+    (
+        "true-snps",
+        "true_snps",
+        "Comma separated ground truth SNP names",
+    ),
+    ("seed", "seed", "RNG seed"),
+    ("checkpoint", "checkpoint_fp", "Path for checkpoints"),
+    (
+        "checkpoint-every",
+        "checkpoint_every",
+        "Iterations between checkpoints",
+    ),
+    (
+        "pheromones",
+        "pheromone_fp",
+        "Path for the final pheromone matrix",
+    ),
+    (
+        "importance",
+        "importance_fp",
+        "Path for the SNP importance ranking",
+    ),
+    (
+        "top-edges",
+        "top_edges",
+        "Number of pheromone edges to export",
+    ),
+    (
+        "min-call-rate",
+        "min_call_rate",
+        "Remove SNPs called in fewer samples",
+    ),
+    ("min-maf", "min_maf", "Remove SNPs with a lower MAF"),
+    (
+        "min-hwe-p",
+        "min_hwe_p",
+        "Remove SNPs with a lower HWE p-value in controls",
+    ),
+    (
+        "qc-report",
+        "qc_report_fp",
+        "Path for the list of removed SNPs",
+    ),
+    (
+        "ld-map",
+        "ld_map_fp",
+        "PLINK .bim or .map file of SNP positions",
+    ),
+    (
+        "ld-window-bp",
+        "ld_window_bp",
+        "Only check LD between SNPs this close",
+    ),
+    (
+        "max-ld-r2",
+        "max_ld_r2",
+        "Prune SNPs in LD above this r^2 before the search",
+    ),
+    (
+        "path-max-r2",
+        "path_max_r2",
+        "Never put SNPs in LD above this r^2 in one path",
+    ),
+    (
+        "holdout",
+        "holdout_fraction",
+        "Fraction of samples held out to replicate the top solutions",
+    ),
+    (
+        "alpha",
+        "alpha",
+        "Mark solutions significant below this corrected p-value",
+    ),
+    (
+        "correction",
+        "correction",
+        "Multiple testing correction compared to alpha, bonferroni or fdr",
+    ),
+    (
+        "significant-only",
+        "significant_only",
+        "Only report significant solutions",
+    ),
+    (
+        "test-statistic",
+        "test_statistic",
+        "Test stat for reported p-values, chi_square or g_test",
+    ),
+    (
+        "sparse-cells",
+        "sparse_cells",
+        "Keep, drop or merge genotype cells below min_expected",
+    ),
+    (
+        "min-expected",
+        "min_expected",
+        "Expected count below which a genotype cell is sparse",
+    ),
+    (
+        "monte-carlo-iters",
+        "monte_carlo_iters",
+        "Permuted tables for Monte Carlo p-values",
+    ),
+];
+
+// the PARAM_ARGS that say how to read a dataset, for subcommands that
+// read one without the other parameters
+const LOAD_ARGS: &[&str] = &[
+    "data-delimiter",
+    "has-header",
+    "phenotype-col",
+    "sample-id-cols",
+];
+
+fn param_arg(spec: &(&'static str, &'static str, &'static str)) -> Arg<'static, 'static> {
+    let (name, key, help) = spec;
+
+    Arg::with_name(name)
+        .long(name)
+        .takes_value(true)
+        .value_name(key)
+        .help(help)
+}
+
+fn load_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    PARAM_ARGS
+        .iter()
+        .filter(|(name, _, _)| LOAD_ARGS.contains(name))
+        .map(param_arg)
+        .collect()
+}
+
+fn param_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    PARAM_ARGS.iter().map(param_arg).collect()
+}
+
+// parses the value of a command line arg, if it was given
+fn parse_arg<T: FromStr>(matches: &ArgMatches, name: &str) -> Result<Option<T>>
+where
+    T::Err: std::fmt::Display,
+{
+    match matches.value_of(name) {
+        Some(val) => val.parse::<T>().map(Some).map_err(|why| {
+            AntsnapError::Usage(format!("invalid value '{}' for --{}: {}", val, name, why))
+        }),
+        None => Ok(None),
+    }
+}
+
+// layers the command line overrides on top of params
+fn apply_overrides(params: &mut AlgoParams, matches: &ArgMatches) -> Result<()> {
+    if let Some(val) = parse_arg(matches, "data")? {
+        params.data_fp = Some(val);
+    }
+    if let Some(val) = parse_arg(matches, "cache")? {
+        params.cache_fp = Some(val);
+    }
+    if let Some(val) = parse_arg(matches, "data-delimiter")? {
+        params.data_delimiter = Some(val);
+    }
+    if let Some(val) = parse_arg(matches, "has-header")? {
+        params.has_header = Some(val);
+    }
+    if let Some(val) = parse_arg(matches, "phenotype-col")? {
+        params.phenotype_col = Some(val);
+    }
+    if let Some(val) = parse_arg::<String>(matches, "sample-id-cols")? {
+        params.sample_id_cols = Some(val.split(',').map(|s| s.trim().to_owned()).collect());
+    }
+    if let Some(val) = parse_arg(matches, "init-pheromone-val")? {
+        params.init_pheromone_val = Some(val);
+    }
+    if let Some(val) = parse_arg(matches, "t-0")? {
+        params.t_0 = Some(val);
+    }
+    if let Some(val) = parse_arg(matches, "evap-coeff")? {
+        params.evap_coeff = Some(val);
+    }
+    if let Some(val) = parse_arg(matches, "epis-dim")? {
+        params.epis_dim = Some(val);
+    }
+    if let Some(val) = parse_arg(matches, "num-ants")? {
+        params.num_ants = Some(val);
+    }
+    if let Some(val) = parse_arg(matches, "max-iters")? {
+        params.max_iters = Some(val);
+    }
+    if let Some(val) = parse_arg(matches, "lambda")? {
+        params.lambda = Some(val);
+    }
+    if let Some(val) = parse_arg(matches, "patience")? {
+        params.patience = Some(val);
+    }
+    if let Some(val) = parse_arg(matches, "min-entropy")? {
+        params.min_entropy = Some(val);
+    }
+    if let Some(val) = parse_arg(matches, "max-time-secs")? {
+        params.max_time_secs = Some(val);
+    }
+    if let Some(val) = parse_arg(matches, "trace")? {
+        params.trace_fp = Some(val);
+    }
+    if let Some(val) = parse_arg::<String>(matches, "true-snps")? {
+        params.true_snps = Some(val.split(',').map(|s| s.trim().to_owned()).collect());
+    }
+    if let Some(val) = parse_arg(matches, "seed")? {
+        params.seed = Some(val);
+    }
+    if let Some(val) = parse_arg(matches, "checkpoint")? {
+        params.checkpoint_fp = Some(val);
+    }
+    if let Some(val) = parse_arg(matches, "checkpoint-every")? {
+        params.checkpoint_every = Some(val);
+    }
+    if let Some(val) = parse_arg(matches, "pheromones")? {
+        params.pheromone_fp = Some(val);
+    }
+    if let Some(val) = parse_arg(matches, "importance")? {
+        params.importance_fp = Some(val);
+    }
+    if let Some(val) = parse_arg(matches, "top-edges")? {
+        params.top_edges = Some(val);
+    }
+    if let Some(val) = parse_arg(matches, "min-call-rate")? {
+        params.min_call_rate = Some(val);
+    }
+    if let Some(val) = parse_arg(matches, "min-maf")? {
+        params.min_maf = Some(val);
+    }
+    if let Some(val) = parse_arg(matches, "min-hwe-p")? {
+        params.min_hwe_p = Some(val);
+    }
+    if let Some(val) = parse_arg(matches, "qc-report")? {
+        params.qc_report_fp = Some(val);
+    }
+    if let Some(val) = parse_arg(matches, "ld-map")? {
+        params.ld_map_fp = Some(val);
+    }
+    if let Some(val) = parse_arg(matches, "ld-window-bp")? {
+        params.ld_window_bp = Some(val);
+    }
+    if let Some(val) = parse_arg(matches, "max-ld-r2")? {
+        params.max_ld_r2 = Some(val);
+    }
+    if let Some(val) = parse_arg(matches, "path-max-r2")? {
+        params.path_max_r2 = Some(val);
+    }
+    if let Some(val) = parse_arg(matches, "holdout")? {
+        params.holdout_fraction = Some(val);
+    }
+    if let Some(val) = parse_arg(matches, "alpha")? {
+        params.alpha = Some(val);
+    }
+    if let Some(val) = parse_arg(matches, "correction")? {
+        params.correction = Some(val);
+    }
+    if let Some(val) = parse_arg(matches, "significant-only")? {
+        params.significant_only = Some(val);
+    }
+    if let Some(val) = parse_arg(matches, "test-statistic")? {
+        params.test_statistic = Some(val);
+    }
+    if let Some(val) = parse_arg(matches, "sparse-cells")? {
+        params.sparse_cells = Some(val);
+    }
+    if let Some(val) = parse_arg(matches, "min-expected")? {
+        params.min_expected = Some(val);
+    }
+    if let Some(val) = parse_arg(matches, "monte-carlo-iters")? {
+        params.monte_carlo_iters = Some(val);
+    }
+
+    params.validate()
+}
+
+// simulation parameters as (arg name, default, help). epis_dim and the
+// seed are added by the subcommands, benchmark takes them from the
+// algorithm parameters
+const SIM_ARGS: &[(&str, &str, &str)] = &[
+    (
+        "num-snps",
+        "100",
+        "Number of SNPs, including the model SNPs",
+    ),
+    ("num-samples", "1600", "Number of samples"),
+    ("case-ratio", "0.5", "Proportion of samples that are cases"),
+    ("maf-min", "0.05", "Smallest minor allele frequency"),
+    ("maf-max", "0.5", "Largest minor allele frequency"),
+    (
+        "heritability",
+        "0.1",
+        "Heritability of the penetrance model",
+    ),
+];
+
+fn sim_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    let mut args: Vec<Arg<'a, 'b>> = SIM_ARGS
+        .iter()
+        .map(|(name, default, help)| {
+            Arg::with_name(name)
+                .long(name)
+                .takes_value(true)
+                .default_value(default)
+                .help(help)
+        })
+        .collect();
+    args.push(
+        Arg::with_name("marginal-effects")
+            .long("marginal-effects")
+            .help(
+                "Allow main effects and lower order interactions, \
+                 by default the model is purely epistatic",
+            ),
+    );
+
+    args
+}
+
+fn parse_sim_params(matches: &ArgMatches, epis_dim: usize, seed: Option<u64>) -> Result<SimParams> {
+    Ok(SimParams {
+        num_snps: parse_arg(matches, "num-snps")?.unwrap(),
+        num_samples: parse_arg(matches, "num-samples")?.unwrap(),
+        case_ratio: parse_arg(matches, "case-ratio")?.unwrap(),
+        maf_min: parse_arg(matches, "maf-min")?.unwrap(),
+        maf_max: parse_arg(matches, "maf-max")?.unwrap(),
+        epis_dim,
+        heritability: parse_arg(matches, "heritability")?.unwrap(),
+        pure_epistasis: !matches.is_present("marginal-effects"),
+        seed,
+    })
+}
+
+// args shared by the subcommands that take algorithm parameters
+fn config_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    let mut args = vec![Arg::with_name("config")
+        .short("c")
+        .long("config")
+        .takes_value(true)
+        .long_help(
+            "Path to a TOML config file. Parameters given on the \
+             command line override the ones in the file",
+        )];
+    args.extend(param_args());
+
+    args
+}
+
+// the config file, or defaults, with command line overrides applied
+fn load_params(matches: &ArgMatches) -> Result<ResolvedParams> {
+    let mut cfg: Config = get_default_config();
+
+    if let Some(cfg_path_str) = matches.value_of("config") {
+        let cfg_path = PathBuf::from(cfg_path_str);
+        cfg = load_config(&cfg_path)?;
+    }
+
+    apply_overrides(&mut cfg.algo, matches)?;
+
+    Ok(cfg.algo.resolve())
+}
+
+// prints how far through a dataset loading is, on one line
+fn report_progress(progress: &LoadProgress) {
+    eprint!(
+        "\rLoaded {} samples ({:.0}%)",
+        progress.rows,
+        progress.fraction() * 100.0
+    );
+    if progress.bytes_read == progress.total_bytes {
+        eprintln!();
+    }
+}
+
+// loads the dataset named by data_fp, through cache_fp if it is set
+fn load_params_data(params: &ResolvedParams) -> Result<(Genotypes, Matrix, Vec<String>)> {
+    let data_fp: &str = params.data_fp.as_deref().ok_or_else(|| {
+        AntsnapError::Usage("no dataset given, set data_fp in the config or pass --data".to_owned())
+    })?;
+
+    let options: LoadOptions = params.load_options();
+
+    match params.cache_fp.as_ref() {
+        Some(cache_fp) => load_data_cached(data_fp, cache_fp, &options, report_progress),
+        None => load_data_with_progress(data_fp, &options, report_progress),
+    }
+}
+
+// removes the SNPs that fail QC, writing the QC report if qc_report_fp
+// is set
+fn qc_data(
+    x: Genotypes,
+    y: &Matrix,
+    header: Vec<String>,
+    params: &ResolvedParams,
+) -> Result<(Genotypes, Vec<String>, QcResult)> {
+    let n_snps: usize = x.1;
+
+    let (x, header_kept, qc) = apply_qc(x, y, header.to_owned(), params)?;
+
+    if !qc.removed.is_empty() {
+        println!("QC removed {} of {} SNPs", qc.removed.len(), n_snps);
+    }
+
+    if let Some(fp) = params.qc_report_fp.as_ref() {
+        write_qc_report(&qc, &header, fp)?;
+    }
+
+    Ok((x, header_kept, qc))
+}
+
+// loads the dataset and removes the SNPs that fail QC
+fn load_qc_data(params: &ResolvedParams) -> Result<(Genotypes, Matrix, Vec<String>)> {
+    let (x, y, header) = load_params_data(params)?;
+    let (x, header, _) = qc_data(x, &y, header, params)?;
+
+    Ok((x, y, header))
+}
+
+// holds out the replication samples if holdout_fraction is set, then
+// runs QC on the discovery samples only, so the held out samples play
+// no part in choosing the SNPs. the replication genotypes keep the
+// SNPs QC kept
+fn holdout_qc_data(
+    x: Genotypes,
+    y: Matrix,
+    header: Vec<String>,
+    params: &ResolvedParams,
+) -> Result<(Genotypes, Matrix, Vec<String>, Option<(Genotypes, Matrix)>)> {
+    let (x, y, holdout): (Genotypes, Matrix, Option<(Genotypes, Matrix)>) =
+        match params.holdout_fraction {
+            Some(fraction) => {
+                let split: HoldoutSplit = stratified_split(&y, fraction, params.seed)?;
+                println!(
+                    "Holding out {} of {} samples for replication",
+                    split.replication.len(),
+                    y.0.len()
+                );
+                let (discovery, replication) = split.apply(&x, &y);
+                (discovery.0, discovery.1, Some(replication))
+            }
+            None => (x, y, None),
+        };
+
+    let (x, header, qc) = qc_data(x, &y, header, params)?;
+
+    let holdout: Option<(Genotypes, Matrix)> = holdout.map(|(x_rep, y_rep)| {
+        if qc.removed.is_empty() {
+            (x_rep, y_rep)
+        } else {
+            (column_subset(&x_rep, &qc.kept), y_rep)
+        }
+    });
+
+    Ok((x, y, header, holdout))
+}
+
+fn build_app() -> App<'static, 'static> {
+    App::new("antsnap")
         .version("0.1")
         .author("William Gasper <wkg@williamgasper.com>")
-        .arg(
-            Arg::with_name("config")
-                .short("c")
-                .long("config")
-                .default_value("")
-                .long_help("Path to a TOML config file"),
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(
+            SubCommand::with_name("run")
+                .about("Search for interacting SNPs with ant colony optimization")
+                .args(&config_args())
+                .arg(
+                    Arg::with_name("resume")
+                        .long("resume")
+                        .long_help("Continue from the checkpoint at checkpoint_fp"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("score")
+                .about("Score SNP sets given by name and print their contingency tables")
+                .args(&config_args())
+                .arg(
+                    Arg::with_name("snps")
+                        .long("snps")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .required(true)
+                        .value_name("SNP,SNP,...")
+                        .help("Comma separated SNP names, may be repeated"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("exhaustive")
+                .about("Score every combination of epis_dim SNPs")
+                .args(&config_args())
+                .arg(
+                    Arg::with_name("top")
+                        .long("top")
+                        .takes_value(true)
+                        .default_value("30")
+                        .help("Number of combinations to report"),
+                ),
         )
-        .get_matches();
+        .subcommand(
+            SubCommand::with_name("convert")
+                .about("Rewrite a dataset with a different delimiter or as binary")
+                .arg(
+                    Arg::with_name("data")
+                        .long("data")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Path to the input dataset"),
+                )
+                .arg(
+                    Arg::with_name("out")
+                        .long("out")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Path for the converted dataset"),
+                )
+                .arg(
+                    Arg::with_name("delimiter")
+                        .long("delimiter")
+                        .takes_value(true)
+                        .possible_values(&["tab", "space", "comma"])
+                        .default_value("tab")
+                        .help("Field delimiter of the output"),
+                )
+                .args(&load_args())
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["text", "binary"])
+                        .default_value("text")
+                        .help("Write delimited text or an antsnap binary dataset"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("simulate")
+                .about("Simulate a case-control dataset with an embedded interaction")
+                .arg(
+                    Arg::with_name("out")
+                        .long("out")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Path for the simulated dataset"),
+                )
+                .args(&sim_args())
+                .arg(
+                    Arg::with_name("epis-dim")
+                        .long("epis-dim")
+                        .takes_value(true)
+                        .default_value("3")
+                        .help("Number of SNPs in the penetrance model"),
+                )
+                .arg(
+                    Arg::with_name("seed")
+                        .long("seed")
+                        .takes_value(true)
+                        .help("RNG seed"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("benchmark")
+                .about("Measure detection power over replicate datasets")
+                .long_about(
+                    "Runs the colony over every dataset in --dir, or over \
+                     --replicates simulated datasets, and reports how often \
+                     the true SNP set is ranked first or in the top --top-n",
+                )
+                .args(&config_args())
+                .args(&benchmark_args()),
+        )
+        .subcommand(
+            SubCommand::with_name("sweep")
+                .about("Compare parameter settings by detection power")
+                .long_about(
+                    "Runs the colony with every combination of the given \
+                     t_0, evap_coeff, lambda and num_ants values over \
+                     replicate datasets, the same way benchmark does, and \
+                     reports power and runtime per setting",
+                )
+                .args(&config_args())
+                .args(&sweep_args()),
+        )
+        .subcommand(
+            SubCommand::with_name("stability")
+                .about("Measure how often SNP sets are found over bootstrap resamples")
+                .long_about(
+                    "Runs the colony on --resamples bootstrap resamples of the \
+                     samples, in parallel, and reports how often each SNP set \
+                     and each SNP is among the top --top-n solutions",
+                )
+                .args(&config_args())
+                .args(&stability_args()),
+        )
+}
 
-    let mut cfg: Config = get_default_config();
+fn run() -> Result<()> {
+    let matches = build_app().get_matches();
 
-    if matches.is_present("config") {
-        let cfg_path_str: &str = matches.value_of("config").unwrap();
-        let cfg_path = PathBuf::from(cfg_path_str);
-        cfg = load_config(&cfg_path);
+    match matches.subcommand() {
+        ("run", Some(sub_matches)) => run_aco(sub_matches),
+        ("score", Some(sub_matches)) => run_score(sub_matches),
+        ("exhaustive", Some(sub_matches)) => run_exhaustive(sub_matches),
+        ("convert", Some(sub_matches)) => run_convert(sub_matches),
+        ("simulate", Some(sub_matches)) => run_simulate(sub_matches),
+        ("benchmark", Some(sub_matches)) => run_benchmark_cmd(sub_matches),
+        ("sweep", Some(sub_matches)) => run_sweep_cmd(sub_matches),
+        ("stability", Some(sub_matches)) => run_stability_cmd(sub_matches),
+        _ => unreachable!("clap requires a subcommand"),
+    }
+}
+
+fn run_aco(matches: &ArgMatches) -> Result<()> {
+    let params: ResolvedParams = load_params(matches)?;
+
+    let (x, y, header) = load_params_data(&params)?;
+
+    // the colony only sees the discovery samples when some are held out
+    let (x, y, header, holdout) = holdout_qc_data(x, y, header, &params)?;
+
+    let runner = AcoRunner::new(x, y, header, &params)?;
+
+    println!("Effective parameters:\n{}\n", runner.describe_params());
+
+    let result: AcoResult = if matches.is_present("resume") {
+        let checkpoint_fp: &str = params.checkpoint_fp.as_deref().ok_or_else(|| {
+            AntsnapError::Usage("--resume requires checkpoint_fp to be set".to_owned())
+        })?;
+        let checkpoint: Checkpoint = load_checkpoint(checkpoint_fp)?;
+        println!("Resuming from iteration {}", checkpoint.iter);
+        runner.run_from(Some(checkpoint))?
+    } else {
+        runner.run()?
+    };
+
+    println!("Stopped: {}", result.stop_reason);
+
+    if let Some(fp) = params.pheromone_fp.as_ref() {
+        write_pheromones(&result.pheromones, runner.header(), fp, params.top_edges)?;
+    }
+
+    if let Some(fp) = params.importance_fp.as_ref() {
+        let importance = snp_importance(&result.pheromones, &result.visit_counts);
+        write_importance(&importance, runner.header(), fp)?;
+    }
+
+    // the solutions in the report, the significant ones if only those
+    // are reported
+    let reported: Vec<Solution> = match params.alpha {
+        Some(alpha) => {
+            let (x, y) = runner.data();
+            let report: SignificanceReport =
+                assess_solutions(&result.solutions, &result.evaluated, x, y, alpha, &params);
+
+            println!(
+                "\nSearch space: {} sets, {} evaluated, alpha = {} ({})\nTest: {}",
+                report.search_space,
+                report.evaluated,
+                alpha,
+                params.correction,
+                params.association_test().describe()
+            );
+
+            let kept: Vec<(&Solution, &Significance)> = result
+                .solutions
+                .iter()
+                .zip(report.solutions.iter())
+                .filter(|(_, sig)| sig.significant || !params.significant_only)
+                .take(N_SOLUTIONS_TO_REPORT)
+                .collect();
+
+            println!("\nCHI SQUARE TEST STATS");
+            for (solution, sig) in kept.iter() {
+                println!(
+                    "Path: {:?}\tX2 test stat: {}\tSamples: {}\tTimes found: {}\t\
+                     p-value: {:e}\tBonferroni: {:e}\tq-value: {:e}\tSignificant: {}",
+                    solution.snps,
+                    solution.score,
+                    solution.n_samples,
+                    solution.times_found,
+                    sig.stats.p_value,
+                    sig.bonferroni,
+                    sig.q_value,
+                    if sig.significant { "yes" } else { "no" }
+                );
+            }
+
+            kept.into_iter()
+                .map(|(solution, _)| solution.to_owned())
+                .collect()
+        }
+        None => {
+            println!("\nCHI SQUARE TEST STATS");
+            for solution in result.solutions.iter().take(N_SOLUTIONS_TO_REPORT) {
+                println!(
+                    "Path: {:?}\tX2 test stat: {}\tSamples: {}\tTimes found: {}",
+                    solution.snps, solution.score, solution.n_samples, solution.times_found
+                );
+            }
+
+            result
+                .solutions
+                .iter()
+                .take(N_SOLUTIONS_TO_REPORT)
+                .cloned()
+                .collect()
+        }
+    };
+
+    if let Some((x_rep, y_rep)) = holdout.as_ref() {
+        let replications: Vec<Replication> = replicate_solutions(
+            &reported,
+            runner.data(),
+            (x_rep, y_rep),
+            &params.association_test(),
+        );
+
+        println!("\nREPLICATION");
+        for rep in replications.iter() {
+            println!(
+                "Path: {:?}\tDiscovery stat: {}\tp-value: {:e}\tSamples: {}\t\
+                 Replication stat: {}\tp-value: {:e}\tSamples: {}",
+                rep.snps,
+                rep.discovery.statistic,
+                rep.discovery.p_value,
+                rep.discovery.n_samples,
+                rep.replication.statistic,
+                rep.replication.p_value,
+                rep.replication.n_samples
+            );
+        }
     }
 
-    aco(&cfg);
+    // Check to see what the Chi square test stat is of the true
+    // solution (make sure GAMETES modeling is working correctly
+    if let Some(test_stat) = runner.true_solution_score() {
+        println!("True sol test stat: {}", test_stat);
+    }
+
+    Ok(())
+}
+
+fn run_score(matches: &ArgMatches) -> Result<()> {
+    let params: ResolvedParams = load_params(matches)?;
+    let (x, y, header) = load_params_data(&params)?;
+    let test: AssociationTest = params.association_test();
+
+    for snps_arg in matches.values_of("snps").unwrap() {
+        let names: Vec<String> = snps_arg.split(',').map(|s| s.trim().to_owned()).collect();
+
+        println!("{}", inspect_snp_set(&x, &y, &header, &names, &test)?);
+    }
+
+    Ok(())
+}
+
+fn run_exhaustive(matches: &ArgMatches) -> Result<()> {
+    let params: ResolvedParams = load_params(matches)?;
+    let top_n: usize = parse_arg(matches, "top")?.unwrap();
+
+    let (x, y, header) = load_qc_data(&params)?;
+
+    if params.epis_dim > x.1 {
+        return Err(AntsnapError::invalid_param(
+            "epis_dim",
+            &format!("is larger than the number of SNPs ({})", x.1),
+        ));
+    }
+
+    println!("CHI SQUARE TEST STATS");
+    for (path, score) in exhaustive_search(&x, &y, params.epis_dim, top_n).iter() {
+        let snps: Vec<&str> = path
+            .iter()
+            .map(|snp| header.get(*snp).unwrap().as_str())
+            .collect();
+        println!(
+            "Path: {:?}\tX2 test stat: {}\tSamples: {}",
+            snps,
+            score,
+            count_complete(&x, path)
+        );
+    }
+
+    Ok(())
+}
+
+fn run_convert(matches: &ArgMatches) -> Result<()> {
+    let data_fp: &str = matches.value_of("data").unwrap();
+    let out_fp: &str = matches.value_of("out").unwrap();
+
+    let mut load_params = AlgoParams::default();
+    apply_overrides(&mut load_params, matches)?;
+    let options: LoadOptions = load_params.resolve().load_options();
+
+    if matches.value_of("format").unwrap() == "binary" {
+        // a binary input has no source to stamp
+        let source: SourceStamp = if is_binary_dataset(data_fp) {
+            SourceStamp::default()
+        } else {
+            source_stamp(data_fp, &options)?
+        };
+        let (store, header) = load_store(data_fp, &options, report_progress)?;
+
+        return write_store(&store, &header, source, out_fp);
+    }
+
+    let delimiter: &str = match matches.value_of("delimiter").unwrap() {
+        "space" => " ",
+        "comma" => ",",
+        _ => "\t",
+    };
+
+    let (x, y, header) = load_data_with_progress(data_fp, &options, report_progress)?;
+    write_data(out_fp, &x, &y, &header, delimiter)
+}
+
+fn run_simulate(matches: &ArgMatches) -> Result<()> {
+    let params: SimParams = parse_sim_params(
+        matches,
+        parse_arg(matches, "epis-dim")?.unwrap(),
+        parse_arg(matches, "seed")?,
+    )?;
+
+    let data: SimulatedData = simulate(&params)?;
+    write_simulated(&data, matches.value_of("out").unwrap())?;
+
+    let model: &PenetranceModel = &data.model;
+    println!("Prevalence: {}", model.prevalence);
+    println!("Heritability: {}", model.heritability);
+    println!("MAFs: {:?}", model.mafs);
+    println!("\ngenotype\tpenetrance");
+    for (cell, penetrance) in model.penetrances.iter().enumerate() {
+        println!(
+            "{}\t{:.4}",
+            genotype_label(cell, params.epis_dim),
+            penetrance
+        );
+    }
+
+    Ok(())
+}
+
+// args for choosing the replicate datasets of benchmark and sweep
+fn replicate_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    let mut args = vec![
+        Arg::with_name("dir")
+            .long("dir")
+            .takes_value(true)
+            .help("Directory of replicate datasets, simulated if not given"),
+        Arg::with_name("replicates")
+            .long("replicates")
+            .takes_value(true)
+            .default_value("10")
+            .help("Number of datasets to simulate"),
+        Arg::with_name("sim-seed")
+            .long("sim-seed")
+            .takes_value(true)
+            .help("Seed of the first simulated dataset"),
+        Arg::with_name("top-n")
+            .long("top-n")
+            .takes_value(true)
+            .default_value("10")
+            .help("Rank the true set must reach to count for top-N power"),
+    ];
+    args.extend(sim_args());
+
+    args
+}
+
+fn benchmark_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    let mut args = replicate_args();
+    args.extend(vec![
+        Arg::with_name("label")
+            .long("label")
+            .takes_value(true)
+            .default_value("default")
+            .help("Label for the row appended to the summary table"),
+        Arg::with_name("summary")
+            .long("summary")
+            .takes_value(true)
+            .help("TSV to append the summary row to"),
+        Arg::with_name("results")
+            .long("results")
+            .takes_value(true)
+            .help("TSV for the per-replicate results"),
+    ]);
+
+    args
+}
+
+// the swept parameters as (arg name, help)
+const SWEEP_ARGS: &[(&str, &str)] = &[
+    ("t-0-values", "Values of t_0"),
+    ("evap-coeff-values", "Values of evap_coeff"),
+    ("lambda-values", "Values of lambda"),
+    ("num-ants-values", "Values of num_ants"),
+];
+
+fn sweep_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    let mut args: Vec<Arg<'a, 'b>> = SWEEP_ARGS
+        .iter()
+        .map(|(name, help)| {
+            Arg::with_name(name)
+                .long(name)
+                .takes_value(true)
+                .value_name("a,b,c or start:end:count")
+                .help(help)
+        })
+        .collect();
+    args.extend(replicate_args());
+    args.extend(vec![
+        Arg::with_name("search")
+            .long("search")
+            .takes_value(true)
+            .possible_values(&["grid", "random", "halving"])
+            .default_value("grid")
+            .help("How settings are chosen and run"),
+        Arg::with_name("samples")
+            .long("samples")
+            .takes_value(true)
+            .default_value("10")
+            .help("Number of settings tried by random search"),
+        Arg::with_name("eta")
+            .long("eta")
+            .takes_value(true)
+            .default_value("3")
+            .help("Reduction factor for successive halving"),
+        Arg::with_name("out")
+            .long("out")
+            .takes_value(true)
+            .help("TSV for the per-setting results"),
+    ]);
+
+    args
+}
+
+fn stability_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("resamples")
+            .long("resamples")
+            .takes_value(true)
+            .default_value("50")
+            .help("Number of bootstrap resamples"),
+        Arg::with_name("top-n")
+            .long("top-n")
+            .takes_value(true)
+            .default_value("10")
+            .help("Solutions per resample that count as selected"),
+        Arg::with_name("out")
+            .long("out")
+            .takes_value(true)
+            .help("TSV for the per-set frequencies"),
+        Arg::with_name("snp-out")
+            .long("snp-out")
+            .takes_value(true)
+            .help("TSV for the per-SNP frequencies"),
+    ]
+}
+
+// the replicate datasets named by the benchmark args
+fn benchmark_data(matches: &ArgMatches, params: &ResolvedParams) -> Result<BenchmarkData> {
+    match matches.value_of("dir") {
+        Some(dir) => BenchmarkData::from_dir(dir),
+        None => Ok(BenchmarkData::Simulated {
+            params: parse_sim_params(matches, params.epis_dim, parse_arg(matches, "sim-seed")?)?,
+            replicates: parse_arg(matches, "replicates")?.unwrap(),
+        }),
+    }
+}
+
+fn run_benchmark_cmd(matches: &ArgMatches) -> Result<()> {
+    let params: ResolvedParams = load_params(matches)?;
+    let top_n: usize = parse_arg(matches, "top-n")?.unwrap();
+    let label: &str = matches.value_of("label").unwrap();
+
+    let data: BenchmarkData = benchmark_data(matches, &params)?;
+
+    let results: Vec<ReplicateResult> = run_benchmark(&data, &params, |result| {
+        let true_rank: String = match result.true_rank {
+            Some(rank) => rank.to_string(),
+            None => "not found".to_owned(),
+        };
+        println!(
+            "{}\ttrue rank: {}\truntime: {:.2}s",
+            result.name, true_rank, result.runtime_secs
+        );
+    })?;
+
+    let summary: BenchmarkSummary = summarize(&results, top_n);
+
+    println!("\n{}", SUMMARY_HEADER);
+    println!("{}", summary_row(&summary, label));
+
+    if let Some(fp) = matches.value_of("results") {
+        write_replicate_results(&results, fp)?;
+    }
+
+    if let Some(fp) = matches.value_of("summary") {
+        append_summary(&summary, label, fp)?;
+    }
+
+    Ok(())
+}
+
+fn run_sweep_cmd(matches: &ArgMatches) -> Result<()> {
+    let params: ResolvedParams = load_params(matches)?;
+    let top_n: usize = parse_arg(matches, "top-n")?.unwrap();
+
+    let values = |name: &str, key: &str| match matches.value_of(name) {
+        Some(spec) => parse_sweep_values(key, spec),
+        None => Ok(Vec::new()),
+    };
+
+    let space = SweepSpace {
+        t_0: values("t-0-values", "t_0")?,
+        evap_coeff: values("evap-coeff-values", "evap_coeff")?,
+        lambda: values("lambda-values", "lambda")?,
+        num_ants: match matches.value_of("num-ants-values") {
+            Some(spec) => parse_sweep_counts("num_ants", spec)?,
+            None => Vec::new(),
+        },
+    };
+
+    let strategy: SearchStrategy = match matches.value_of("search").unwrap() {
+        "random" => SearchStrategy::Random(parse_arg(matches, "samples")?.unwrap()),
+        "halving" => SearchStrategy::Halving(parse_arg(matches, "eta")?.unwrap()),
+        _ => SearchStrategy::Grid,
+    };
+
+    let data: BenchmarkData = benchmark_data(matches, &params)?;
+
+    let sweep: Vec<SweepResult> = run_sweep(
+        &data,
+        &params,
+        &space,
+        strategy,
+        top_n,
+        params.seed,
+        |setting, result| {
+            let true_rank: String = match result.true_rank {
+                Some(rank) => rank.to_string(),
+                None => "not found".to_owned(),
+            };
+            println!(
+                "{}\t{}\ttrue rank: {}\truntime: {:.2}s",
+                describe_setting(setting),
+                result.name,
+                true_rank,
+                result.runtime_secs
+            );
+        },
+    )?;
+
+    println!(
+        "\nt_0\tevap_coeff\tlambda\tnum_ants\t{}",
+        SUMMARY_FIELDS_HEADER
+    );
+    for setting in sweep.iter() {
+        println!("{}", sweep_row(setting));
+    }
+
+    if let Some(fp) = matches.value_of("out") {
+        write_sweep(&sweep, fp)?;
+    }
+
+    Ok(())
+}
+
+fn run_stability_cmd(matches: &ArgMatches) -> Result<()> {
+    let params: ResolvedParams = load_params(matches)?;
+    let resamples: usize = parse_arg(matches, "resamples")?.unwrap();
+    let top_n: usize = parse_arg(matches, "top-n")?.unwrap();
+
+    let (x, y, header) = load_qc_data(&params)?;
+
+    println!(
+        "Running {} resamples of {} samples, counting the top {} solutions",
+        resamples,
+        y.0.len(),
+        top_n
+    );
+    let result: StabilityResult = run_stability(&x, &y, &header, &params, resamples, top_n)?;
+
+    println!("\nSET STABILITY");
+    for set in result.sets.iter().take(N_SOLUTIONS_TO_REPORT) {
+        println!(
+            "Path: {:?}\tFrequency: {:.3}\tTest stat: {}\tp-value: {:e}\tSamples: {}",
+            set.snps, set.frequency, set.stats.statistic, set.stats.p_value, set.stats.n_samples
+        );
+    }
+
+    println!("\nSNP STABILITY");
+    for snp in result.snps.iter().take(N_SOLUTIONS_TO_REPORT) {
+        println!("{}\tFrequency: {:.3}", snp.name, snp.frequency);
+    }
+
+    if let Some(fp) = matches.value_of("out") {
+        write_set_stability(&result, fp)?;
+    }
+
+    if let Some(fp) = matches.value_of("snp-out") {
+        write_snp_stability(&result, fp)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_app_0() {
+        // clap panics on args registered twice when the app is built,
+        // parse a command line for each subcommand
+        let command_lines: Vec<Vec<&str>> = vec![
+            vec!["run", "--data", "data.txt"],
+            vec!["score", "--snps", "N1,N2"],
+            vec!["exhaustive", "--top", "5"],
+            vec!["convert", "--data", "data.txt", "--out", "out.txt"],
+            vec!["simulate", "--out", "sim.txt", "--epis-dim", "2"],
+            vec!["benchmark", "--epis-dim", "2", "--replicates", "2"],
+            vec!["sweep", "--epis-dim", "2", "--t-0-values", "0.5,0.9"],
+            vec!["stability", "--resamples", "5"],
+        ];
+
+        for args in command_lines.iter() {
+            let mut command_line: Vec<&str> = vec!["antsnap"];
+            command_line.extend(args.iter());
+
+            let matches = build_app().get_matches_from_safe(command_line).unwrap();
+            let (name, sub_matches) = matches.subcommand();
+            assert_eq!(name, args[0]);
+            assert!(sub_matches.is_some());
+        }
+
+        let matches = build_app()
+            .get_matches_from_safe(vec!["antsnap", "simulate", "--out", "sim.txt"])
+            .unwrap();
+        let sub_matches = matches.subcommand_matches("simulate").unwrap();
+        assert_eq!(sub_matches.value_of("epis-dim"), Some("3"));
+    }
+
+    #[test]
+    fn apply_overrides_0() {
+        let cfg_path = std::env::temp_dir().join("antsnap_test_apply_overrides_0.toml");
+        std::fs::write(
+            &cfg_path,
+            "[algo]\ndata_fp = 'data.txt'\nnum_ants = 77\nlambda = 3.0\n",
+        )
+        .unwrap();
+        let cfg_fp: &str = cfg_path.to_str().unwrap();
+
+        let parse = |args: &[&str]| -> ResolvedParams {
+            let mut command_line: Vec<&str> = vec!["antsnap", "run", "-c", cfg_fp];
+            command_line.extend(args.iter());
+
+            let matches = build_app().get_matches_from_safe(command_line).unwrap();
+            load_params(matches.subcommand_matches("run").unwrap()).unwrap()
+        };
+
+        // unset flags keep the config values
+        let params: ResolvedParams = parse(&[]);
+        assert_eq!(params.num_ants, 77);
+        assert_eq!(params.lambda, 3.0);
+        assert_eq!(params.data_fp.as_deref(), Some("data.txt"));
+
+        // a flag overrides its config value and leaves the rest
+        let params: ResolvedParams = parse(&["--num-ants", "12", "--data", "other.txt"]);
+        assert_eq!(params.num_ants, 12);
+        assert_eq!(params.lambda, 3.0);
+        assert_eq!(params.data_fp.as_deref(), Some("other.txt"));
+    }
+
+    #[test]
+    fn holdout_qc_data_0() {
+        let n_rows: usize = 40;
+        let y: Matrix = ((0..n_rows).map(|row| (row % 2) as f64).collect(), 1);
+
+        let params = ResolvedParams {
+            holdout_fraction: Some(0.25),
+            seed: Some(5),
+            min_maf: Some(0.01),
+            ..ResolvedParams::default()
+        };
+        let split: HoldoutSplit = stratified_split(&y, 0.25, params.seed).unwrap();
+
+        // N0 only varies in the held out samples, N1 varies in every
+        // sample
+        let mut x: Genotypes = (Vec::new(), 2);
+        for row in 0..n_rows {
+            let n0: u8 = if split.replication.contains(&row) {
+                2
+            } else {
+                0
+            };
+            x.0.extend(vec![n0, (row % 3) as u8]);
+        }
+        let header: Vec<String> = vec!["N0".to_owned(), "N1".to_owned(), "Class".to_owned()];
+
+        // on every sample N0 would pass, on the discovery samples it is
+        // monomorphic
+        let (_, _, qc) = apply_qc(x.to_owned(), &y, header.to_owned(), &params).unwrap();
+        assert_eq!(qc.kept, vec![0, 1]);
+
+        let (x_disc, y_disc, header_kept, holdout) =
+            holdout_qc_data(x, y, header, &params).unwrap();
+        let (x_rep, y_rep) = holdout.unwrap();
+
+        assert_eq!(header_kept, vec!["N1".to_owned(), "Class".to_owned()]);
+        assert_eq!(x_disc.1, 1);
+        assert_eq!(x_disc.0.len(), split.discovery.len());
+        assert_eq!(y_disc.0.len(), split.discovery.len());
+        assert_eq!(x_rep.1, 1);
+        assert_eq!(
+            x_rep.0,
+            split
+                .replication
+                .iter()
+                .map(|row| (row % 3) as u8)
+                .collect::<Vec<u8>>()
+        );
+        assert_eq!(y_rep.0.len(), split.replication.len());
+    }
 }
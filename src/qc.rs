@@ -0,0 +1,191 @@
+use std::fmt;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::BufWriter;
+
+use crate::config::*;
+use crate::error::*;
+use crate::ld::*;
+use crate::stats::*;
+use crate::store::*;
+use crate::utils::*;
+
+type SNP = usize;
+type Element = f64;
+type Matrix = (Vec<Element>, usize);
+type Genotypes = (Vec<u8>, usize);
+
+// why a SNP was removed, with the value that failed the threshold
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QcReason {
+    CallRate(f64),
+    Maf(f64),
+    // p-value of the exact HWE test in controls
+    Hwe(f64),
+    // largest r^2 with a nearby SNP that was kept
+    Ld(f64),
+}
+
+impl fmt::Display for QcReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            QcReason::CallRate(v) => write!(f, "call_rate\t{}", v),
+            QcReason::Maf(v) => write!(f, "maf\t{}", v),
+            QcReason::Hwe(v) => write!(f, "hwe_p\t{:e}", v),
+            QcReason::Ld(v) => write!(f, "ld_r2\t{}", v),
+        }
+    }
+}
+
+// outcome of QC. kept maps each column of the filtered data back to its
+// column in the original data, removed holds the original column of
+// every removed SNP and why it was removed
+#[derive(Debug, Clone, PartialEq)]
+pub struct QcResult {
+    pub kept: Vec<SNP>,
+    pub removed: Vec<(SNP, QcReason)>,
+}
+
+// call rate, minor allele frequency and genotype counts of a SNP. counts
+// are over all samples and over controls only
+struct SnpStats {
+    call_rate: f64,
+    maf: f64,
+    control_counts: [usize; 3],
+}
+
+fn snp_stats(x: &Genotypes, y: &Matrix, snp: SNP) -> SnpStats {
+    let n_samples: usize = x.0.len() / x.1;
+    let mut counts: [usize; 3] = [0; 3];
+    let mut control_counts: [usize; 3] = [0; 3];
+
+    for row_idx in 0..n_samples {
+        let genotype: u8 = x.0.get(row_idx * x.1 + snp).unwrap().to_owned();
+        if genotype == MISSING_CODE {
+            continue;
+        }
+
+        let g: usize = (genotype as usize).min(2);
+        counts[g] += 1;
+        if y.0.get(row_idx).unwrap() == &0.0 {
+            control_counts[g] += 1;
+        }
+    }
+
+    let n_called: usize = counts.iter().sum();
+    let maf: f64 = if n_called == 0 {
+        0.0
+    } else {
+        let freq: f64 = (counts[1] + 2 * counts[2]) as f64 / (2 * n_called) as f64;
+        freq.min(1.0 - freq)
+    };
+
+    SnpStats {
+        call_rate: n_called as f64 / n_samples.max(1) as f64,
+        maf,
+        control_counts,
+    }
+}
+
+// checks every SNP against the thresholds that are set, in the order
+// call rate, MAF, HWE. a SNP is removed for the first one it fails
+pub fn run_qc(x: &Genotypes, y: &Matrix, params: &ResolvedParams) -> QcResult {
+    let mut kept: Vec<SNP> = Vec::with_capacity(x.1);
+    let mut removed: Vec<(SNP, QcReason)> = Vec::new();
+
+    for snp in 0..x.1 {
+        let stats: SnpStats = snp_stats(x, y, snp);
+
+        let reason: Option<QcReason> = if params
+            .min_call_rate
+            .is_some_and(|min| stats.call_rate < min)
+        {
+            Some(QcReason::CallRate(stats.call_rate))
+        } else if params.min_maf.is_some_and(|min| stats.maf < min) {
+            Some(QcReason::Maf(stats.maf))
+        } else if let Some(min) = params.min_hwe_p {
+            let counts = stats.control_counts;
+            let p: f64 = hwe_exact_p(counts[1], counts[0], counts[2]);
+            if p < min {
+                Some(QcReason::Hwe(p))
+            } else {
+                None
+            }
+        } else {
+            None
+        };
+
+        match reason {
+            Some(reason) => removed.push((snp, reason)),
+            None => kept.push(snp),
+        }
+    }
+
+    QcResult { kept, removed }
+}
+
+impl QcResult {
+    // the kept SNPs' columns and header. names after the SNP columns,
+    // like Class, are kept
+    pub fn apply(&self, x: &Genotypes, header: &[String]) -> (Genotypes, Vec<String>) {
+        let mut kept_header: Vec<String> = self
+            .kept
+            .iter()
+            .filter_map(|snp| header.get(*snp).cloned())
+            .collect();
+        kept_header.extend(header.iter().skip(x.1).cloned());
+
+        (column_subset(x, &self.kept), kept_header)
+    }
+}
+
+// runs QC, then LD pruning among the SNPs that pass if max_ld_r2 is
+// set, and returns the filtered data. if no thresholds are set every SNP
+// is kept
+pub fn apply_qc(
+    x: Genotypes,
+    y: &Matrix,
+    header: Vec<String>,
+    params: &ResolvedParams,
+) -> Result<(Genotypes, Vec<String>, QcResult)> {
+    let mut qc: QcResult = run_qc(&x, y, params);
+
+    if let (Some(max_r2), Some(map_fp)) = (params.max_ld_r2, params.ld_map_fp.as_ref()) {
+        let positions: Vec<SnpPosition> = load_positions(map_fp, &header, x.1)?;
+        let pruned: Vec<(SNP, f64)> =
+            ld_prune(&x, &qc.kept, &positions, params.ld_window_bp, max_r2);
+
+        qc.kept.retain(|snp| !pruned.iter().any(|p| p.0 == *snp));
+        qc.removed
+            .extend(pruned.into_iter().map(|(snp, r2)| (snp, QcReason::Ld(r2))));
+        qc.removed.sort_by_key(|r| r.0);
+    }
+
+    if qc.kept.is_empty() {
+        return Err(AntsnapError::Usage(format!("QC removed all {} SNPs", x.1)));
+    }
+
+    if qc.removed.is_empty() {
+        return Ok((x, header, qc));
+    }
+
+    let (x_kept, header_kept) = qc.apply(&x, &header);
+
+    Ok((x_kept, header_kept, qc))
+}
+
+// writes the removed SNPs as a TSV. column is the SNP's 0-based column
+// in the original data
+pub fn write_qc_report(qc: &QcResult, header: &[String], fp: &str) -> Result<()> {
+    let io_err = |why| AntsnapError::io(fp, why);
+    let mut writer = BufWriter::new(File::create(fp).map_err(io_err)?);
+
+    writeln!(writer, "snp\tcolumn\treason\tvalue").map_err(io_err)?;
+
+    for (snp, reason) in qc.removed.iter() {
+        let name: String = header.get(*snp).cloned().unwrap_or_else(|| snp.to_string());
+        writeln!(writer, "{}\t{}\t{}", name, snp, reason).map_err(io_err)?;
+    }
+
+    writer.flush().map_err(io_err)
+}
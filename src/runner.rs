@@ -0,0 +1,531 @@
+use rand::prelude::*;
+use rand_pcg::Pcg32;
+use rayon::prelude::*; // 1.5.0
+
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+use crate::algo::*;
+use crate::checkpoint::*;
+use crate::config::*;
+use crate::error::*;
+use crate::ld::*;
+use crate::trace::*;
+use crate::utils::*;
+
+type SNP = usize;
+type Element = f64;
+type Matrix = (Vec<Element>, usize);
+type Genotypes = (Vec<u8>, usize);
+
+// top proportion of solutions to boost pheromone vals for
+const PROPORTION_TO_SELECT: f64 = 0.05;
+// number of solutions to retain per round
+const N_SOLUTIONS_TO_RETAIN: usize = 10;
+// minimum increase in the best score that counts as an improvement
+const MIN_IMPROVEMENT: f64 = 1e-9;
+
+// a distinct SNP set found by the colony
+#[derive(Debug, Clone, PartialEq)]
+pub struct Solution {
+    // canonical path, column indices into the dataset
+    pub path: Vec<SNP>,
+    pub snps: Vec<String>,
+    pub score: f64,
+    // samples with a genotype for every SNP in the set, the ones the
+    // score was computed from
+    pub n_samples: usize,
+    // how many times an ant built this set
+    pub times_found: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct AcoResult {
+    // retained solutions, best first
+    pub solutions: Vec<Solution>,
+    pub iterations: Vec<IterationStats>,
+    pub pheromones: Matrix,
+    // how many ant paths included each SNP
+    pub visit_counts: Vec<usize>,
+    pub stop_reason: StopReason,
+    // every distinct SNP set an ant built, as canonical paths in order
+    pub evaluated: Vec<Vec<SNP>>,
+}
+
+// runs the ant colony over a dataset. this is the library entry point,
+// nothing is printed and the results are returned
+pub struct AcoRunner {
+    x: Genotypes,
+    y: Matrix,
+    header: Vec<String>,
+    params: ResolvedParams,
+    // SNPs each SNP is in LD with above path_max_r2, empty if it is not
+    // set
+    linked: Vec<Vec<SNP>>,
+}
+
+// number of the best paths of an iteration whose edges are reinforced,
+// the rest only evaporate. the proportion has to be applied as a float,
+// truncating it to usize first made this 0 and no edge was ever reinforced
+pub fn elite_count(n_paths: usize) -> usize {
+    (n_paths as f64 * PROPORTION_TO_SELECT) as usize
+}
+
+impl AcoRunner {
+    // x is the genotype matrix, y the phenotype column and header the
+    // SNP names, as returned by load_data. the parameters are validated
+    // here, including against the dataset
+    pub fn new(
+        x: Genotypes,
+        y: Matrix,
+        header: Vec<String>,
+        params: &ResolvedParams,
+    ) -> Result<AcoRunner> {
+        params.validate()?;
+
+        if x.1 == 0 || !x.0.len().is_multiple_of(x.1) {
+            return Err(AntsnapError::Shape(
+                "genotype matrix is empty or has a partial row".to_owned(),
+            ));
+        }
+
+        if x.0.len() / x.1 != y.0.len() {
+            return Err(AntsnapError::Shape(format!(
+                "genotype matrix has {} rows but there are {} phenotypes",
+                x.0.len() / x.1,
+                y.0.len()
+            )));
+        }
+
+        if header.len() < x.1 {
+            return Err(AntsnapError::Shape(format!(
+                "header names {} columns but there are {} SNPs",
+                header.len(),
+                x.1
+            )));
+        }
+
+        if params.epis_dim > x.1 {
+            return Err(AntsnapError::invalid_param(
+                "epis_dim",
+                &format!(
+                    "is {} but the dataset only has {} SNPs",
+                    params.epis_dim, x.1
+                ),
+            ));
+        }
+
+        let linked: Vec<Vec<SNP>> = match params.path_max_r2 {
+            Some(max_r2) => {
+                let positions: Option<Vec<SnpPosition>> = match params.ld_map_fp.as_ref() {
+                    Some(fp) => Some(load_positions(fp, &header, x.1)?),
+                    None => None,
+                };
+                ld_partners(&x, positions.as_deref(), params.ld_window_bp, max_r2)
+            }
+            None => Vec::new(),
+        };
+
+        Ok(AcoRunner {
+            x,
+            y,
+            header,
+            params: params.to_owned(),
+            linked,
+        })
+    }
+
+    pub fn params(&self) -> &ResolvedParams {
+        &self.params
+    }
+
+    // the dataset's dimensions and the parameters the run will use, as
+    // name = value lines
+    pub fn describe_params(&self) -> String {
+        format!(
+            "num_snps = {}\nnum_samples = {}\n{}",
+            self.x.1,
+            self.y.0.len(),
+            self.params.describe()
+        )
+    }
+
+    pub fn header(&self) -> &[String] {
+        &self.header
+    }
+
+    // the genotypes and phenotypes the colony searches
+    pub fn data(&self) -> (&Genotypes, &Matrix) {
+        (&self.x, &self.y)
+    }
+
+    // the Chi square test stat of the ground truth SNPs on simulated
+    // data, see get_true_solution
+    pub fn true_solution_score(&self) -> Option<f64> {
+        get_true_solution(&self.header, &self.params.true_snps)
+            .map(|path| path_x2(&path, &self.x, &self.y))
+    }
+
+    // fingerprint of the search parameters, used to refuse resuming with
+    // a different config. this is everything that changes which SNPs the
+    // ants can visit, how paths are built or scored, or which samples and
+    // SNPs are searched. run length and output settings are left out so a
+    // resumed run can be given a bigger budget
+    pub fn config_hash(&self) -> u64 {
+        let p: &ResolvedParams = &self.params;
+
+        fnv1a_hash(
+            format!(
+                "num_ants={} epis_dim={} t_0={} evap_coeff={} lambda={} \
+                 init_pheromone_val={} seed={:?} min_call_rate={:?} min_maf={:?} \
+                 min_hwe_p={:?} ld_map_fp={:?} ld_window_bp={} max_ld_r2={:?} \
+                 path_max_r2={:?} holdout_fraction={:?} test_statistic={} \
+                 sparse_cells={} min_expected={} monte_carlo_iters={:?}",
+                p.num_ants,
+                p.epis_dim,
+                p.t_0,
+                p.evap_coeff,
+                p.lambda,
+                p.init_pheromone_val,
+                p.seed,
+                p.min_call_rate,
+                p.min_maf,
+                p.min_hwe_p,
+                p.ld_map_fp,
+                p.ld_window_bp,
+                p.max_ld_r2,
+                p.path_max_r2,
+                p.holdout_fraction,
+                p.test_statistic,
+                p.sparse_cells,
+                p.min_expected,
+                p.monte_carlo_iters
+            )
+            .as_bytes(),
+        )
+    }
+
+    pub fn data_hash(&self) -> u64 {
+        hash_dataset(&self.x, &self.y, &self.header)
+    }
+
+    pub fn run(&self) -> Result<AcoResult> {
+        self.run_from(None)
+    }
+
+    // runs the colony, continuing from checkpoint if one is given
+    pub fn run_from(&self, checkpoint: Option<Checkpoint>) -> Result<AcoResult> {
+        let num_snps: usize = self.x.1;
+
+        let true_solution: Option<Vec<SNP>> =
+            get_true_solution(&self.header, &self.params.true_snps);
+
+        // the master RNG, each ant gets its own RNG seeded from this one so
+        // a seeded run is reproducible regardless of thread scheduling
+        let mut rng: Pcg32 = match self.params.seed {
+            Some(seed) => Pcg32::seed_from_u64(seed),
+            None => Pcg32::from_entropy(),
+        };
+
+        // convergence criteria, each is disabled unless set
+        let time_limit: Option<Duration> = self.params.max_time_secs.map(Duration::from_secs_f64);
+
+        let config_hash: u64 = self.config_hash();
+        let data_hash: u64 = self.data_hash();
+
+        // Chi square test stats for every SNP set scored so far, keyed by
+        // the canonical path and shared across iterations
+        let mut score_cache: HashMap<Vec<SNP>, f64> = HashMap::new();
+
+        // how many times each SNP set was built by an ant
+        let mut times_found: HashMap<Vec<SNP>, usize> = HashMap::new();
+
+        // retain the top solutions, as canonical paths
+        let mut top_solutions: HashSet<Vec<SNP>> = HashSet::new();
+
+        let mut visit_counts: Vec<usize> = vec![0; num_snps];
+        let mut iterations: Vec<IterationStats> = Vec::new();
+
+        // init pheromones matrix
+        let mut pheromones: Matrix = init_pheromones(num_snps);
+
+        let start_time = Instant::now();
+        let mut last_iter_time = Duration::from_secs(0);
+        let mut best_score: f64 = f64::NEG_INFINITY;
+        let mut iters_without_improvement: usize = 0;
+        let mut stop_reason = StopReason::MaxIters(self.params.max_iters);
+        let mut start_iter: usize = 0;
+
+        let resumed: bool = checkpoint.is_some();
+
+        if let Some(checkpoint) = checkpoint {
+            let checkpoint_fp: &str = self.params.checkpoint_fp.as_deref().unwrap_or("checkpoint");
+            verify_checkpoint(&checkpoint, checkpoint_fp, config_hash, data_hash)?;
+
+            start_iter = checkpoint.iter;
+            pheromones = checkpoint.pheromones;
+            best_score = checkpoint.best_score;
+            iters_without_improvement = checkpoint.iters_without_improvement;
+            rng = checkpoint.rng;
+            // older checkpoints have no visit counts, they count from here
+            if !checkpoint.visit_counts.is_empty() {
+                visit_counts = checkpoint.visit_counts;
+            }
+
+            for (path, score) in checkpoint.top_solutions.into_iter() {
+                score_cache.insert(path.to_owned(), score);
+                top_solutions.insert(path);
+            }
+            times_found.extend(checkpoint.times_found);
+        }
+
+        // a resumed run continues the trace it wrote before
+        let mut trace_writer: Option<TraceWriter> = match self.params.trace_fp.as_ref() {
+            Some(fp) if resumed => Some(TraceWriter::resume(fp, start_iter)?),
+            Some(fp) => Some(TraceWriter::new(fp)?),
+            None => None,
+        };
+
+        // the time limit is a hard cap. an iteration that is expected to
+        // run past it isn't started, and ants that would start building
+        // after it don't, so a single long iteration stops there too
+        let deadline: Option<Instant> = time_limit.map(|limit| start_time + limit);
+        let past_deadline = || deadline.is_some_and(|d| Instant::now() >= d);
+
+        for iter in start_iter..self.params.max_iters {
+            if let Some(limit) = time_limit {
+                if start_time.elapsed() + last_iter_time > limit {
+                    stop_reason = StopReason::TimeLimit(limit);
+                    break;
+                }
+            }
+
+            let iter_start_time = Instant::now();
+
+            // give each ant its first snp
+            let mut paths: Vec<Vec<SNP>> = init_ants(
+                self.params.num_ants,
+                num_snps,
+                self.params.epis_dim,
+                &mut rng,
+            );
+            let ant_seeds: Vec<u64> = (0..self.params.num_ants).map(|_| rng.gen()).collect();
+
+            paths
+                .par_iter_mut()
+                .zip(ant_seeds.par_iter())
+                .for_each(|(p, seed)| {
+                    if past_deadline() {
+                        return;
+                    }
+
+                    let mut ant_rng = Pcg32::seed_from_u64(seed.to_owned());
+                    expand_path_excluding(
+                        p,
+                        &pheromones,
+                        self.params.epis_dim,
+                        self.params.t_0,
+                        &self.linked,
+                        &mut ant_rng,
+                    );
+                });
+
+            // out of time partway through the ants. the paths that were
+            // built still count, and the run stops after this iteration
+            let out_of_time: bool = past_deadline();
+            if out_of_time {
+                paths.retain(|p| p.len() == self.params.epis_dim);
+
+                if paths.is_empty() {
+                    stop_reason = StopReason::TimeLimit(time_limit.unwrap());
+                    break;
+                }
+            }
+
+            // the order an ant visited SNPs in matters for pheromone updates,
+            // but not for the identity of the solution
+            let canonical_paths: Vec<Vec<SNP>> = paths.iter().map(canonical_path).collect();
+
+            for path in canonical_paths.iter() {
+                *times_found.entry(path.to_owned()).or_insert(0) += 1;
+
+                for snp in path.iter() {
+                    visit_counts[*snp] += 1;
+                }
+            }
+
+            score_new_paths(&canonical_paths, &mut score_cache, &self.x, &self.y);
+
+            let mut losses: Vec<(usize, f64)> = canonical_paths
+                .iter()
+                .enumerate()
+                .map(|(idx, p)| (idx, score_cache.get(p).unwrap().to_owned()))
+                .collect();
+
+            // sort losses
+            losses.sort_by(|a, b| best_first(a.1, b.1));
+
+            // retain the top distinct SNP sets from this iteration
+            let mut retained: HashSet<&Vec<SNP>> = HashSet::new();
+
+            for loss in losses.iter() {
+                if retained.len() >= N_SOLUTIONS_TO_RETAIN {
+                    break;
+                }
+                retained.insert(canonical_paths.get(loss.0).unwrap());
+            }
+
+            for path in retained.into_iter() {
+                top_solutions.insert(path.to_owned());
+            }
+
+            // select top proportion of solutions
+            let partition: usize = elite_count(losses.len());
+
+            // update pheromones
+            for idx in 0..partition {
+                let this_path = paths.get(losses.get(idx).unwrap().0).unwrap();
+                update_pheromones(
+                    &mut pheromones,
+                    this_path,
+                    &self.params.evap_coeff,
+                    &self.params.lambda,
+                    true,
+                );
+            }
+
+            for idx in partition..losses.len() {
+                let this_path = paths.get(losses.get(idx).unwrap().0).unwrap();
+                update_pheromones(
+                    &mut pheromones,
+                    this_path,
+                    &self.params.evap_coeff,
+                    &self.params.lambda,
+                    false,
+                );
+            }
+
+            last_iter_time = iter_start_time.elapsed();
+
+            let iter_best: f64 = losses.first().unwrap().1;
+            let scores: Vec<f64> = losses.iter().map(|l| l.1).collect();
+            let (pheromone_min, pheromone_max) = pheromone_range(&pheromones);
+
+            // distinct sets built this iteration, best first
+            let mut distinct: Vec<(&Vec<SNP>, f64)> = canonical_paths
+                .iter()
+                .collect::<HashSet<&Vec<SNP>>>()
+                .into_iter()
+                .map(|p| (p, score_cache.get(p).unwrap().to_owned()))
+                .collect();
+            distinct.sort_by(|a, b| best_first(a.1, b.1));
+
+            let true_rank: Option<usize> = true_solution
+                .as_ref()
+                .and_then(|t| distinct.iter().position(|d| d.0 == t))
+                .map(|pos| pos + 1);
+
+            let stats = IterationStats {
+                iter,
+                best_score: iter_best,
+                mean_score: scores.iter().sum::<f64>() / scores.len() as f64,
+                median_score: get_median(&scores),
+                distinct_paths: distinct.len(),
+                pheromone_min,
+                pheromone_max,
+                pheromone_entropy: pheromone_entropy(&pheromones),
+                elite_paths: partition,
+                time_secs: last_iter_time.as_secs_f64(),
+                true_rank,
+            };
+
+            if let Some(writer) = trace_writer.as_mut() {
+                writer.write(&stats)?;
+            }
+
+            // check convergence
+            if iter_best > best_score + MIN_IMPROVEMENT {
+                best_score = iter_best;
+                iters_without_improvement = 0;
+            } else {
+                iters_without_improvement += 1;
+            }
+
+            if let Some(fp) = self.params.checkpoint_fp.as_ref() {
+                if (iter + 1) % self.params.checkpoint_every == 0 {
+                    let checkpoint = Checkpoint {
+                        iter: iter + 1,
+                        pheromones: pheromones.to_owned(),
+                        top_solutions: top_solutions
+                            .iter()
+                            .map(|p| (p.to_owned(), score_cache.get(p).unwrap().to_owned()))
+                            .collect(),
+                        times_found: times_found
+                            .iter()
+                            .map(|(p, count)| (p.to_owned(), count.to_owned()))
+                            .collect(),
+                        visit_counts: visit_counts.to_owned(),
+                        best_score,
+                        iters_without_improvement,
+                        rng: rng.clone(),
+                        config_hash,
+                        data_hash,
+                    };
+                    save_checkpoint(&checkpoint, fp)?;
+                }
+            }
+
+            let entropy: f64 = stats.pheromone_entropy;
+            iterations.push(stats);
+
+            if out_of_time {
+                stop_reason = StopReason::TimeLimit(time_limit.unwrap());
+                break;
+            }
+
+            if let Some(n) = self.params.patience {
+                if iters_without_improvement >= n {
+                    stop_reason = StopReason::NoImprovement(n);
+                    break;
+                }
+            }
+
+            if let Some(threshold) = self.params.min_entropy {
+                if entropy < threshold {
+                    stop_reason = StopReason::LowEntropy(entropy);
+                    break;
+                }
+            }
+        }
+
+        // each distinct solution is reported once, along with the number of
+        // times an ant found it
+        let mut solutions: Vec<Solution> = top_solutions
+            .into_iter()
+            .map(|path| Solution {
+                snps: path
+                    .iter()
+                    .map(|s| self.header.get(s.to_owned()).unwrap().to_owned())
+                    .collect(),
+                score: score_cache.get(&path).unwrap().to_owned(),
+                n_samples: count_complete(&self.x, &path),
+                times_found: times_found.get(&path).unwrap().to_owned(),
+                path,
+            })
+            .collect();
+
+        solutions.sort_by(|a, b| best_first(a.score, b.score).then_with(|| a.path.cmp(&b.path)));
+
+        let mut evaluated: Vec<Vec<SNP>> = times_found.into_keys().collect();
+        evaluated.sort_unstable();
+
+        Ok(AcoResult {
+            solutions,
+            iterations,
+            pheromones,
+            visit_counts,
+            stop_reason,
+            evaluated,
+        })
+    }
+}
@@ -0,0 +1,136 @@
+use rayon::prelude::*; // 1.5.0
+
+use std::collections::HashMap;
+
+use crate::algo::*;
+use crate::config::*;
+use crate::contingency::*;
+use crate::runner::*;
+use crate::stats::*;
+
+type SNP = usize;
+type Element = f64;
+type Matrix = (Vec<Element>, usize);
+type Genotypes = (Vec<u8>, usize);
+
+// which adjusted p-value is compared against alpha
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Correction {
+    // over every combination of epis_dim SNPs, C(num_snps, epis_dim)
+    Bonferroni,
+    // Benjamini-Hochberg FDR over the SNP sets the colony evaluated
+    Fdr,
+}
+
+pub const CORRECTION_NAMES: &[&str] = &["bonferroni", "fdr"];
+
+impl Correction {
+    pub fn from_name(name: &str) -> Option<Correction> {
+        match name {
+            "bonferroni" => Some(Correction::Bonferroni),
+            "fdr" => Some(Correction::Fdr),
+            _ => None,
+        }
+    }
+}
+
+// a solution's p-value with both corrections applied
+#[derive(Debug, Clone, PartialEq)]
+pub struct Significance {
+    pub stats: SetStats,
+    pub bonferroni: f64,
+    pub q_value: f64,
+    // the corrected p-value chosen by the Correction is below alpha
+    pub significant: bool,
+}
+
+// the multiple testing burden of a search, see assess_solutions
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignificanceReport {
+    // number of combinations of epis_dim SNPs
+    pub search_space: f64,
+    // number of distinct SNP sets evaluated
+    pub evaluated: usize,
+    // one per solution, in order
+    pub solutions: Vec<Significance>,
+}
+
+// corrects the p-values of solutions for the search that found them.
+// the Bonferroni correction counts every set in the search space, the
+// FDR only the sets in evaluated, which solutions should be among. the
+// sets are tested and corrected as params say, correction must already
+// be validated. evaluated can hold millions of sets, so Monte Carlo
+// p-values are only computed for the solutions themselves. the other
+// sets are ranked for the q-values by their Chi square distribution
+// p-values, and each solution by its own, so no q-value is below the
+// p-value it is reported with
+pub fn assess_solutions(
+    solutions: &[Solution],
+    evaluated: &[Vec<SNP>],
+    x: &Genotypes,
+    y: &Matrix,
+    alpha: f64,
+    params: &ResolvedParams,
+) -> SignificanceReport {
+    let search_space: f64 = n_choose_k(x.1, params.epis_dim);
+    let correction: Correction = Correction::from_name(&params.correction).unwrap();
+    let test: AssociationTest = params.association_test();
+    let asymptotic: AssociationTest = AssociationTest {
+        monte_carlo_iters: None,
+        ..test.to_owned()
+    };
+
+    let solution_stats: Vec<SetStats> = solutions
+        .par_iter()
+        .map(|solution| path_stats(&solution.path, x, y, &test))
+        .collect();
+    let solution_p: HashMap<&Vec<SNP>, f64> = solutions
+        .iter()
+        .zip(solution_stats.iter())
+        .map(|(solution, stats)| (&solution.path, stats.p_value))
+        .collect();
+
+    let p_values: Vec<f64> = evaluated
+        .par_iter()
+        .map(|path| match solution_p.get(path) {
+            Some(p) => p.to_owned(),
+            None => path_stats(path, x, y, &asymptotic).p_value,
+        })
+        .collect();
+    let q_values: HashMap<&Vec<SNP>, f64> = evaluated
+        .iter()
+        .zip(benjamini_hochberg(&p_values))
+        .collect();
+
+    let solutions: Vec<Significance> = solutions
+        .iter()
+        .zip(solution_stats.into_iter())
+        .map(|(solution, stats)| {
+            let bonferroni_p: f64 = bonferroni(stats.p_value, search_space);
+            // the colony's solutions are always among the evaluated sets,
+            // anything else gets the stricter correction
+            let q_value: f64 = match q_values.get(&solution.path) {
+                Some(q) => q.to_owned(),
+                None => bonferroni_p,
+            };
+
+            let adjusted: f64 = match correction {
+                Correction::Bonferroni => bonferroni_p,
+                Correction::Fdr => q_value,
+            };
+
+            Significance {
+                stats,
+                bonferroni: bonferroni_p,
+                q_value,
+                significant: adjusted < alpha,
+            }
+        })
+        .collect();
+
+    SignificanceReport {
+        search_space,
+        evaluated: evaluated.len(),
+        solutions,
+    }
+}
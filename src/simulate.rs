@@ -0,0 +1,391 @@
+use rand::prelude::*;
+use rand_pcg::Pcg32;
+
+use crate::config::MAX_EPIS_DIM;
+use crate::error::*;
+use crate::utils::*;
+
+type Element = f64;
+type Matrix = (Vec<Element>, usize);
+type Genotypes = (Vec<u8>, usize);
+
+// number of random models drawn before giving up on one that can reach
+// the requested heritability with penetrances in [0, 1]
+const N_MODEL_ATTEMPTS: usize = 1000;
+// rounds of removing lower order effects and clipping when drawing a
+// purely epistatic model
+const N_PROJECTION_ROUNDS: usize = 100;
+// prevalences tried when scaling a model, nearest to 0.5 first
+const PREVALENCE_STEP: f64 = 0.01;
+
+// parameters for a simulated case-control dataset. the model SNPs are
+// named M0P0.. and come after the N0.. noise SNPs, like GAMETES output
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimParams {
+    // total number of SNPs, including the epis_dim model SNPs
+    pub num_snps: usize,
+    pub num_samples: usize,
+    // proportion of samples that are cases
+    pub case_ratio: f64,
+    // minor allele frequencies are drawn uniformly from this range
+    pub maf_min: f64,
+    pub maf_max: f64,
+    // number of SNPs in the penetrance model
+    pub epis_dim: usize,
+    // proportion of phenotypic variance explained by the model
+    pub heritability: f64,
+    // if set the model has no main effects or lower order interactions,
+    // the model SNPs can only be found together
+    pub pure_epistasis: bool,
+    pub seed: Option<u64>,
+}
+
+impl Default for SimParams {
+    fn default() -> SimParams {
+        SimParams {
+            num_snps: 100,
+            num_samples: 1600,
+            case_ratio: 0.5,
+            maf_min: 0.05,
+            maf_max: 0.5,
+            epis_dim: 3,
+            heritability: 0.1,
+            pure_epistasis: true,
+            seed: None,
+        }
+    }
+}
+
+impl SimParams {
+    pub fn validate(&self) -> Result<()> {
+        let check = |name: &str, is_valid: bool, expected: &str| {
+            if is_valid {
+                Ok(())
+            } else {
+                Err(AntsnapError::invalid_param(name, expected))
+            }
+        };
+
+        check(
+            "epis_dim",
+            (1..=MAX_EPIS_DIM).contains(&self.epis_dim),
+            &format!("must be between 1 and {}", MAX_EPIS_DIM),
+        )?;
+        check(
+            "num_snps",
+            self.num_snps >= self.epis_dim,
+            "must be at least epis_dim",
+        )?;
+        check("num_samples", self.num_samples > 1, "must be at least 2")?;
+        check(
+            "case_ratio",
+            self.case_ratio > 0.0 && self.case_ratio < 1.0,
+            "must be between 0 and 1, exclusive",
+        )?;
+        check(
+            "maf_min",
+            self.maf_min > 0.0 && self.maf_min <= 0.5,
+            "must be greater than 0 and at most 0.5",
+        )?;
+        check(
+            "maf_max",
+            self.maf_max >= self.maf_min && self.maf_max <= 0.5,
+            "must be at least maf_min and at most 0.5",
+        )?;
+        check(
+            "heritability",
+            self.heritability > 0.0 && self.heritability < 1.0,
+            "must be between 0 and 1, exclusive",
+        )?;
+
+        Ok(())
+    }
+}
+
+// penetrance, P(case | genotypes), for each genotype combination of the
+// model SNPs. combinations are indexed base 3 with the first SNP as the
+// most significant digit, the same as build_contingency_table columns
+#[derive(Debug, Clone, PartialEq)]
+pub struct PenetranceModel {
+    pub mafs: Vec<f64>,
+    pub penetrances: Vec<f64>,
+    pub prevalence: f64,
+    pub heritability: f64,
+}
+
+pub struct SimulatedData {
+    pub x: Genotypes,
+    pub y: Matrix,
+    pub header: Vec<String>,
+    pub model: PenetranceModel,
+}
+
+// genotype frequencies under Hardy-Weinberg equilibrium, indexed by
+// minor allele count
+fn hwe_freqs(maf: f64) -> [f64; 3] {
+    [(1.0 - maf).powi(2), 2.0 * maf * (1.0 - maf), maf.powi(2)]
+}
+
+// genotypes of a cell of a k SNP model, first SNP first
+fn cell_genotypes(cell: usize, k: usize) -> Vec<usize> {
+    let mut genotypes: Vec<usize> = vec![0; k];
+    let mut remainder: usize = cell;
+
+    for idx in (0..k).rev() {
+        genotypes[idx] = remainder % 3;
+        remainder /= 3;
+    }
+
+    genotypes
+}
+
+// population frequency of each cell of the model
+fn cell_weights(mafs: &[f64]) -> Vec<f64> {
+    let k: usize = mafs.len();
+    let freqs: Vec<[f64; 3]> = mafs.iter().map(|maf| hwe_freqs(*maf)).collect();
+
+    (0..3_usize.pow(k as u32))
+        .map(|cell| {
+            cell_genotypes(cell, k)
+                .iter()
+                .enumerate()
+                .map(|(snp, g)| freqs[snp][*g])
+                .product()
+        })
+        .collect()
+}
+
+// removes every effect of a proper subset of the SNPs from vals, leaving
+// only the full k-way interaction. for each subset, smallest first, the
+// weighted mean of vals over the other SNPs is subtracted. centering
+// first means removing one subset's effect never brings back another's
+fn remove_lower_order_effects(vals: &mut [f64], weights: &[f64], k: usize) {
+    let mean: f64 = vals.iter().zip(weights.iter()).map(|(v, w)| v * w).sum();
+    vals.iter_mut().for_each(|v| *v -= mean);
+
+    let mut subsets: Vec<usize> = (1..(1_usize << k) - 1).collect();
+    subsets.sort_by_key(|mask| mask.count_ones());
+
+    let genotypes: Vec<Vec<usize>> = (0..vals.len()).map(|c| cell_genotypes(c, k)).collect();
+
+    for mask in subsets {
+        // key of a cell is its genotypes at the SNPs in the subset
+        let key = |cell: usize| -> usize {
+            (0..k)
+                .filter(|snp| mask & (1 << snp) != 0)
+                .fold(0, |acc, snp| acc * 3 + genotypes[cell][snp])
+        };
+
+        let n_keys: usize = 3_usize.pow(mask.count_ones());
+        let mut sums: Vec<f64> = vec![0.0; n_keys];
+        let mut totals: Vec<f64> = vec![0.0; n_keys];
+
+        for cell in 0..vals.len() {
+            sums[key(cell)] += vals[cell] * weights[cell];
+            totals[key(cell)] += weights[cell];
+        }
+
+        for cell in 0..vals.len() {
+            let total: f64 = totals[key(cell)];
+            if total > 0.0 {
+                vals[cell] -= sums[key(cell)] / total;
+            }
+        }
+    }
+}
+
+// scales zero mean effects to the heritability, at the prevalence
+// nearest 0.5 that keeps every penetrance in [0, 1]. heritability is
+// the variance of the penetrances over K(1 - K)
+fn scale_to_heritability(
+    effects: &[f64],
+    weights: &[f64],
+    heritability: f64,
+) -> Option<(Vec<f64>, f64)> {
+    let variance: f64 = effects
+        .iter()
+        .zip(weights.iter())
+        .map(|(e, w)| e * e * w)
+        .sum();
+
+    if variance <= 0.0 {
+        return None;
+    }
+
+    let max_effect: f64 = effects.iter().cloned().fold(f64::MIN, f64::max);
+    let min_effect: f64 = effects.iter().cloned().fold(f64::MAX, f64::min);
+
+    let n_steps: usize = (0.5 / PREVALENCE_STEP) as usize;
+
+    for step in 0..n_steps {
+        for prevalence in [
+            0.5 - step as f64 * PREVALENCE_STEP,
+            0.5 + step as f64 * PREVALENCE_STEP,
+        ]
+        .iter()
+        {
+            let scale: f64 = (heritability * prevalence * (1.0 - prevalence) / variance).sqrt();
+
+            if prevalence + scale * max_effect <= 1.0 && prevalence + scale * min_effect >= 0.0 {
+                let penetrances: Vec<f64> =
+                    effects.iter().map(|e| prevalence + scale * e).collect();
+                return Some((penetrances, *prevalence));
+            }
+        }
+    }
+
+    None
+}
+
+// draws a random penetrance model for SNPs with the given MAFs
+pub fn random_penetrance_model<R: Rng>(
+    mafs: &[f64],
+    heritability: f64,
+    pure_epistasis: bool,
+    rng: &mut R,
+) -> Result<PenetranceModel> {
+    let k: usize = mafs.len();
+    let weights: Vec<f64> = cell_weights(mafs);
+
+    for _ in 0..N_MODEL_ATTEMPTS {
+        let mut effects: Vec<f64> = (0..weights.len())
+            .map(|_| rng.gen_range(-1.0, 1.0))
+            .collect();
+
+        if pure_epistasis {
+            // removing the lower order effects gives rare genotype
+            // combinations large effects, which caps the heritability.
+            // alternating it with clipping to the starting range keeps
+            // the effects bounded
+            for _ in 0..N_PROJECTION_ROUNDS {
+                remove_lower_order_effects(&mut effects, &weights, k);
+                effects.iter_mut().for_each(|e| *e = e.clamp(-1.0, 1.0));
+            }
+            remove_lower_order_effects(&mut effects, &weights, k);
+        } else {
+            let mean: f64 = effects.iter().zip(weights.iter()).map(|(e, w)| e * w).sum();
+            effects.iter_mut().for_each(|e| *e -= mean);
+        }
+
+        if let Some((penetrances, prevalence)) =
+            scale_to_heritability(&effects, &weights, heritability)
+        {
+            return Ok(PenetranceModel {
+                mafs: mafs.to_vec(),
+                penetrances,
+                prevalence,
+                heritability,
+            });
+        }
+    }
+
+    Err(AntsnapError::invalid_param(
+        "heritability",
+        "is too high for the model SNPs' MAFs, no model was found with every penetrance between 0 and 1. try a lower heritability or a higher maf_min",
+    ))
+}
+
+fn draw_genotype<R: Rng>(maf: f64, rng: &mut R) -> u8 {
+    let mut genotype: u8 = 0;
+
+    for _ in 0..2 {
+        if rng.gen::<f64>() < maf {
+            genotype += 1;
+        }
+    }
+
+    genotype
+}
+
+// simulates a case-control dataset. samples are drawn from the
+// population and kept if their class still needs samples, so cases and
+// controls both follow the penetrance model
+pub fn simulate(params: &SimParams) -> Result<SimulatedData> {
+    params.validate()?;
+
+    let mut rng: Pcg32 = match params.seed {
+        Some(seed) => Pcg32::seed_from_u64(seed),
+        None => Pcg32::from_entropy(),
+    };
+
+    let num_noise: usize = params.num_snps - params.epis_dim;
+    let mafs: Vec<f64> = (0..params.num_snps)
+        .map(|_| {
+            if params.maf_max > params.maf_min {
+                rng.gen_range(params.maf_min, params.maf_max)
+            } else {
+                params.maf_min
+            }
+        })
+        .collect();
+
+    let model: PenetranceModel = random_penetrance_model(
+        &mafs[num_noise..],
+        params.heritability,
+        params.pure_epistasis,
+        &mut rng,
+    )?;
+
+    let num_cases: usize = (params.num_samples as f64 * params.case_ratio).round() as usize;
+    let mut needed: [usize; 2] = [params.num_samples - num_cases, num_cases];
+
+    let mut rows: Vec<(Vec<u8>, f64)> = Vec::with_capacity(params.num_samples);
+
+    while needed[0] + needed[1] > 0 {
+        let model_genotypes: Vec<u8> = mafs[num_noise..]
+            .iter()
+            .map(|maf| draw_genotype(*maf, &mut rng))
+            .collect();
+        let cell: usize = model_genotypes
+            .iter()
+            .fold(0, |acc, g| acc * 3 + *g as usize);
+
+        let status: usize = if rng.gen::<f64>() < model.penetrances[cell] {
+            1
+        } else {
+            0
+        };
+
+        if needed[status] == 0 {
+            continue;
+        }
+        needed[status] -= 1;
+
+        let mut genotypes: Vec<u8> = mafs[..num_noise]
+            .iter()
+            .map(|maf| draw_genotype(*maf, &mut rng))
+            .collect();
+        genotypes.extend(model_genotypes);
+
+        rows.push((genotypes, status as f64));
+    }
+
+    rows.shuffle(&mut rng);
+
+    let mut x: Genotypes = (
+        Vec::with_capacity(params.num_samples * params.num_snps),
+        params.num_snps,
+    );
+    let mut y: Matrix = (Vec::with_capacity(params.num_samples), 1);
+
+    for (genotypes, status) in rows.into_iter() {
+        x.0.extend(genotypes);
+        y.0.push(status);
+    }
+
+    let mut header: Vec<String> = (0..num_noise).map(|idx| format!("N{}", idx)).collect();
+    header.extend((0..params.epis_dim).map(|idx| format!("M0P{}", idx)));
+    header.push("Class".to_owned());
+
+    Ok(SimulatedData {
+        x,
+        y,
+        header,
+        model,
+    })
+}
+
+// writes a simulated dataset in the format load_data reads
+pub fn write_simulated(data: &SimulatedData, fp: &str) -> Result<()> {
+    write_data(fp, &data.x, &data.y, &data.header, "\t")
+}
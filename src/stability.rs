@@ -0,0 +1,223 @@
+use rand::prelude::*;
+use rand_pcg::Pcg32;
+use rayon::prelude::*; // 1.5.0
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::BufWriter;
+
+use crate::algo::*;
+use crate::config::*;
+use crate::contingency::*;
+use crate::error::*;
+use crate::runner::*;
+use crate::utils::*;
+
+type SNP = usize;
+type Element = f64;
+type Matrix = (Vec<Element>, usize);
+type Genotypes = (Vec<u8>, usize);
+
+// how often a SNP set was in the top top_n over the resamples, with its
+// association test on the full dataset
+#[derive(Debug, Clone, PartialEq)]
+pub struct SetStability {
+    pub path: Vec<SNP>,
+    pub snps: Vec<String>,
+    pub count: usize,
+    pub frequency: f64,
+    pub stats: SetStats,
+}
+
+// how often a SNP was in any of the top top_n sets over the resamples
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnpStability {
+    pub snp: SNP,
+    pub name: String,
+    pub count: usize,
+    pub frequency: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StabilityResult {
+    pub resamples: usize,
+    pub top_n: usize,
+    // most stable first
+    pub sets: Vec<SetStability>,
+    pub snps: Vec<SnpStability>,
+}
+
+// a bootstrap resample of the rows of y, drawn with replacement from the
+// controls and from the cases separately so it keeps their counts
+pub fn bootstrap_rows(y: &Matrix, rng: &mut Pcg32) -> Vec<usize> {
+    let mut rows: Vec<usize> = Vec::with_capacity(y.0.len());
+
+    for is_case in [false, true].iter() {
+        let class_rows: Vec<usize> = (0..y.0.len())
+            .filter(|row| (y.0[*row] != 0.0) == *is_case)
+            .collect();
+
+        for _ in 0..class_rows.len() {
+            rows.push(class_rows[rng.gen_range(0, class_rows.len())]);
+        }
+    }
+
+    rows.sort_unstable();
+
+    rows
+}
+
+// runs the colony on resamples bootstrap resamples of the dataset, in
+// parallel, and counts how often each SNP set and each SNP is among the
+// top_n solutions. resample i uses seed + i for both the resample and the
+// colony if a seed is set. the trace and checkpoint files are not
+// written, the resamples would overwrite each other's
+pub fn run_stability(
+    x: &Genotypes,
+    y: &Matrix,
+    header: &[String],
+    params: &ResolvedParams,
+    resamples: usize,
+    top_n: usize,
+) -> Result<StabilityResult> {
+    if resamples == 0 {
+        return Err(AntsnapError::Usage(
+            "stability needs at least one resample".to_owned(),
+        ));
+    }
+
+    let top_paths: Vec<Vec<Vec<SNP>>> = (0..resamples)
+        .into_par_iter()
+        .map(|idx| -> Result<Vec<Vec<SNP>>> {
+            let mut rep_params: ResolvedParams = params.to_owned();
+            rep_params.seed = params.seed.map(|seed| seed.wrapping_add(idx as u64));
+            rep_params.trace_fp = None;
+            rep_params.checkpoint_fp = None;
+
+            let mut rng: Pcg32 = match rep_params.seed {
+                Some(seed) => Pcg32::seed_from_u64(seed),
+                None => Pcg32::from_entropy(),
+            };
+            let rows: Vec<usize> = bootstrap_rows(y, &mut rng);
+
+            let runner = AcoRunner::new(
+                row_subset(x, &rows),
+                row_subset(y, &rows),
+                header.to_vec(),
+                &rep_params,
+            )?;
+            let result: AcoResult = runner.run()?;
+
+            Ok(result
+                .solutions
+                .into_iter()
+                .take(top_n)
+                .map(|s| s.path)
+                .collect())
+        })
+        .collect::<Result<Vec<Vec<Vec<SNP>>>>>()?;
+
+    let mut set_counts: HashMap<Vec<SNP>, usize> = HashMap::new();
+    let mut snp_counts: Vec<usize> = vec![0; x.1];
+
+    for paths in top_paths.iter() {
+        let mut seen: Vec<bool> = vec![false; x.1];
+
+        for path in paths.iter() {
+            *set_counts.entry(path.to_owned()).or_insert(0) += 1;
+
+            for snp in path.iter() {
+                seen[*snp] = true;
+            }
+        }
+
+        for (snp, in_top) in seen.iter().enumerate() {
+            if *in_top {
+                snp_counts[snp] += 1;
+            }
+        }
+    }
+
+    let n: f64 = resamples as f64;
+    let test: AssociationTest = params.association_test();
+
+    let mut sets: Vec<SetStability> = set_counts
+        .into_iter()
+        .map(|(path, count)| SetStability {
+            snps: path.iter().map(|snp| header[*snp].to_owned()).collect(),
+            stats: path_stats(&path, x, y, &test),
+            path,
+            count,
+            frequency: count as f64 / n,
+        })
+        .collect();
+    sets.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then(best_first(a.stats.statistic, b.stats.statistic))
+            .then(a.path.cmp(&b.path))
+    });
+
+    let mut snps: Vec<SnpStability> = snp_counts
+        .iter()
+        .enumerate()
+        .filter(|(_, count)| **count > 0)
+        .map(|(snp, count)| SnpStability {
+            snp,
+            name: header[snp].to_owned(),
+            count: *count,
+            frequency: *count as f64 / n,
+        })
+        .collect();
+    snps.sort_by(|a, b| b.count.cmp(&a.count).then(a.snp.cmp(&b.snp)));
+
+    Ok(StabilityResult {
+        resamples,
+        top_n,
+        sets,
+        snps,
+    })
+}
+
+// writes one row per SNP set as a TSV, most stable first
+pub fn write_set_stability(result: &StabilityResult, fp: &str) -> Result<()> {
+    let io_err = |why| AntsnapError::io(fp, why);
+    let mut writer = BufWriter::new(File::create(fp).map_err(io_err)?);
+
+    writeln!(
+        writer,
+        "snps\tcount\tfrequency\tstatistic\tp_value\tsamples"
+    )
+    .map_err(io_err)?;
+
+    for set in result.sets.iter() {
+        writeln!(
+            writer,
+            "{}\t{}\t{}\t{}\t{:e}\t{}",
+            set.snps.join(","),
+            set.count,
+            set.frequency,
+            set.stats.statistic,
+            set.stats.p_value,
+            set.stats.n_samples
+        )
+        .map_err(io_err)?;
+    }
+
+    writer.flush().map_err(io_err)
+}
+
+// writes one row per SNP as a TSV, most stable first
+pub fn write_snp_stability(result: &StabilityResult, fp: &str) -> Result<()> {
+    let io_err = |why| AntsnapError::io(fp, why);
+    let mut writer = BufWriter::new(File::create(fp).map_err(io_err)?);
+
+    writeln!(writer, "snp\tcount\tfrequency").map_err(io_err)?;
+
+    for snp in result.snps.iter() {
+        writeln!(writer, "{}\t{}\t{}", snp.name, snp.count, snp.frequency).map_err(io_err)?;
+    }
+
+    writer.flush().map_err(io_err)
+}
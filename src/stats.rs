@@ -0,0 +1,223 @@
+type Element = f64;
+type Matrix = (Vec<Element>, usize);
+
+// convergence settings for the incomplete gamma function
+const GAMMA_MAX_ITERS: usize = 1000;
+const GAMMA_EPS: f64 = 1e-15;
+const GAMMA_TINY: f64 = 1e-300;
+
+// Lanczos approximation coefficients, g = 7, n = 9
+const LANCZOS_G: f64 = 7.0;
+const LANCZOS_COEFFS: [f64; 9] = [
+    0.999_999_999_999_809_9,
+    676.520_368_121_885_1,
+    -1_259.139_216_722_402_8,
+    771.323_428_777_653_1,
+    -176.615_029_162_140_6,
+    12.507_343_278_686_905,
+    -0.138_571_095_265_720_12,
+    9.984_369_578_019_572e-6,
+    1.505_632_735_149_311_6e-7,
+];
+
+// natural log of the gamma function for x > 0
+pub fn ln_gamma(x: f64) -> f64 {
+    if x < 0.5 {
+        // reflection formula
+        let pi = std::f64::consts::PI;
+        return (pi / (pi * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+
+    let x = x - 1.0;
+    let mut sum: f64 = LANCZOS_COEFFS[0];
+    for (idx, coeff) in LANCZOS_COEFFS.iter().enumerate().skip(1) {
+        sum += coeff / (x + idx as f64);
+    }
+
+    let t: f64 = x + LANCZOS_G + 0.5;
+
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
+// lower regularized incomplete gamma function P(a, x) by its series
+// expansion, converges quickly for x < a + 1
+fn gamma_p_series(a: f64, x: f64) -> f64 {
+    let mut term: f64 = 1.0 / a;
+    let mut sum: f64 = term;
+    let mut denom: f64 = a;
+
+    for _ in 0..GAMMA_MAX_ITERS {
+        denom += 1.0;
+        term *= x / denom;
+        sum += term;
+        if term.abs() < sum.abs() * GAMMA_EPS {
+            break;
+        }
+    }
+
+    sum * (-x + a * x.ln() - ln_gamma(a)).exp()
+}
+
+// upper regularized incomplete gamma function Q(a, x) by its continued
+// fraction (modified Lentz), converges quickly for x >= a + 1
+fn gamma_q_continued_fraction(a: f64, x: f64) -> f64 {
+    let mut b: f64 = x + 1.0 - a;
+    let mut c: f64 = 1.0 / GAMMA_TINY;
+    let mut d: f64 = 1.0 / b;
+    let mut h: f64 = d;
+
+    for i in 1..=GAMMA_MAX_ITERS {
+        let an: f64 = -(i as f64) * (i as f64 - a);
+        b += 2.0;
+        d = an * d + b;
+        if d.abs() < GAMMA_TINY {
+            d = GAMMA_TINY;
+        }
+        c = b + an / c;
+        if c.abs() < GAMMA_TINY {
+            c = GAMMA_TINY;
+        }
+        d = 1.0 / d;
+        let delta: f64 = d * c;
+        h *= delta;
+        if (delta - 1.0).abs() < GAMMA_EPS {
+            break;
+        }
+    }
+
+    (-x + a * x.ln() - ln_gamma(a)).exp() * h
+}
+
+// upper regularized incomplete gamma function Q(a, x)
+pub fn gamma_q(a: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        1.0
+    } else if x < a + 1.0 {
+        1.0 - gamma_p_series(a, x)
+    } else {
+        gamma_q_continued_fraction(a, x)
+    }
+}
+
+// survival function of the Chi square distribution, the p-value of a
+// test stat with df degrees of freedom
+pub fn chi_square_sf(stat: f64, df: usize) -> f64 {
+    if df == 0 {
+        return 1.0;
+    }
+
+    gamma_q(df as f64 / 2.0, stat / 2.0).clamp(0.0, 1.0)
+}
+
+// degrees of freedom of a Chi square test on a contingency table. rows
+// and columns with no observations are left out, the same as they
+// contribute nothing to the test stat
+pub fn contingency_df(table: &Matrix) -> usize {
+    let n_cols: usize = table.1;
+    let n_rows: usize = table.0.len() / n_cols;
+
+    let nonempty_rows: usize = table
+        .0
+        .chunks(n_cols)
+        .filter(|row| row.iter().sum::<f64>() > 0.0)
+        .count();
+    let nonempty_cols: usize = (0..n_cols)
+        .filter(|col| (0..n_rows).any(|row| table.0[row * n_cols + col] > 0.0))
+        .count();
+
+    nonempty_rows.saturating_sub(1) * nonempty_cols.saturating_sub(1)
+}
+
+// exact test of Hardy-Weinberg equilibrium for one SNP's genotype
+// counts (Wigginton et al., 2005). the p-value is the probability, given
+// the allele counts, of heterozygote counts no more likely than the one
+// observed
+pub fn hwe_exact_p(n_hets: usize, n_hom_1: usize, n_hom_2: usize) -> f64 {
+    let n_hom_rare: usize = n_hom_1.min(n_hom_2);
+    let n_hom_common: usize = n_hom_1.max(n_hom_2);
+    let n_genotypes: usize = n_hets + n_hom_rare + n_hom_common;
+
+    if n_genotypes == 0 {
+        return 1.0;
+    }
+
+    let rare_copies: usize = 2 * n_hom_rare + n_hets;
+    let mut het_probs: Vec<f64> = vec![0.0; rare_copies + 1];
+
+    // start from the most likely heterozygote count, which has the same
+    // parity as rare_copies
+    let mut mid: usize = rare_copies * (2 * n_genotypes - rare_copies) / (2 * n_genotypes);
+    if mid % 2 != rare_copies % 2 {
+        mid += 1;
+    }
+    het_probs[mid] = 1.0;
+
+    let mut hets: usize = mid;
+    let mut hom_rare: usize = (rare_copies - mid) / 2;
+    let mut hom_common: usize = n_genotypes - hets - hom_rare;
+    while hets >= 2 {
+        het_probs[hets - 2] = het_probs[hets] * (hets * (hets - 1)) as f64
+            / (4.0 * (hom_rare + 1) as f64 * (hom_common + 1) as f64);
+        hom_rare += 1;
+        hom_common += 1;
+        hets -= 2;
+    }
+
+    hets = mid;
+    hom_rare = (rare_copies - mid) / 2;
+    hom_common = n_genotypes - hets - hom_rare;
+    while hets + 2 <= rare_copies {
+        het_probs[hets + 2] = het_probs[hets] * 4.0 * hom_rare as f64 * hom_common as f64
+            / ((hets + 2) * (hets + 1)) as f64;
+        hom_rare -= 1;
+        hom_common -= 1;
+        hets += 2;
+    }
+
+    let total: f64 = het_probs.iter().sum();
+    // allow for rounding when comparing against the observed count
+    let observed: f64 = het_probs[n_hets] * (1.0 + 1e-10);
+
+    let p: f64 = het_probs.iter().filter(|p| **p <= observed).sum::<f64>() / total;
+
+    p.min(1.0)
+}
+
+// number of ways to choose k of n, as a float since the search space of
+// a large panel overflows an integer
+pub fn n_choose_k(n: usize, k: usize) -> f64 {
+    if k > n {
+        return 0.0;
+    }
+
+    let k: usize = k.min(n - k);
+
+    (1..=k).fold(1.0, |acc, i| acc * (n - k + i) as f64 / i as f64)
+}
+
+// Bonferroni adjusted p-value for one of n_tests tests
+pub fn bonferroni(p: f64, n_tests: f64) -> f64 {
+    (p * n_tests).min(1.0)
+}
+
+// Benjamini-Hochberg adjusted p-values (q-values), in the order of
+// p_values. each is the smallest FDR at which its test is rejected
+pub fn benjamini_hochberg(p_values: &[f64]) -> Vec<f64> {
+    let n_tests: f64 = p_values.len() as f64;
+
+    let mut order: Vec<usize> = (0..p_values.len()).collect();
+    order.sort_by(|a, b| p_values[*a].total_cmp(&p_values[*b]));
+
+    let mut q_values: Vec<f64> = vec![1.0; p_values.len()];
+    let mut running_min: f64 = 1.0;
+
+    // step up from the largest p-value so q-values are monotone in p.
+    // the factor is at least 1, so rounding never puts a q-value below
+    // its p-value
+    for (rank, idx) in order.iter().enumerate().rev() {
+        running_min = running_min.min(p_values[*idx] * (n_tests / (rank + 1) as f64));
+        q_values[*idx] = running_min;
+    }
+
+    q_values
+}
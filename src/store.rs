@@ -0,0 +1,807 @@
+use memmap::Mmap;
+use std::convert::TryInto;
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use crate::error::*;
+use crate::utils::*;
+
+type Element = f64;
+type Matrix = (Vec<Element>, usize);
+type Genotypes = (Vec<u8>, usize);
+
+// code for a missing genotype in a GenotypeStore
+pub const MISSING_CODE: u8 = 3;
+
+// rows between progress reports while streaming a text dataset
+pub const PROGRESS_ROWS: usize = 10_000;
+
+// first bytes of a binary dataset
+const MAGIC: &[u8; 8] = b"ANTSNAP\0";
+const FORMAT_VERSION: u32 = 3;
+
+// genotypes stored one byte per call, 0, 1, 2 or MISSING_CODE, row
+// major. an eighth of the size of the same genotypes as f64s
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GenotypeStore {
+    pub codes: Vec<u8>,
+    pub num_snps: usize,
+    pub phenotype: Vec<f64>,
+    pub sample_ids: Vec<String>,
+}
+
+// how far stream_data has got through a file
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoadProgress {
+    pub rows: usize,
+    pub bytes_read: u64,
+    pub total_bytes: u64,
+}
+
+impl LoadProgress {
+    pub fn fraction(&self) -> f64 {
+        if self.total_bytes == 0 {
+            1.0
+        } else {
+            self.bytes_read as f64 / self.total_bytes as f64
+        }
+    }
+}
+
+// the code for a genotype value, None if it isn't 0, 1, 2 or missing
+pub fn genotype_code(genotype: f64) -> Option<u8> {
+    if is_missing(genotype) {
+        Some(MISSING_CODE)
+    } else if genotype == 0.0 || genotype == 1.0 || genotype == 2.0 {
+        Some(genotype as u8)
+    } else {
+        None
+    }
+}
+
+pub fn code_genotype(code: u8) -> f64 {
+    if code == MISSING_CODE {
+        MISSING_GENOTYPE
+    } else {
+        code as f64
+    }
+}
+
+// expands genotype codes to values, for the few SNPs a model is
+// trained on. the search itself works on the codes
+pub fn genotype_values(x: &Genotypes) -> Matrix {
+    (x.0.iter().map(|code| code_genotype(*code)).collect(), x.1)
+}
+
+// ID of the sample in row idx, for files without sample IDs
+pub fn default_sample_id(idx: usize) -> String {
+    format!("sample{}", idx + 1)
+}
+
+impl GenotypeStore {
+    pub fn num_samples(&self) -> usize {
+        self.phenotype.len()
+    }
+
+    // the genotype and phenotype matrices the algorithm works on. the
+    // genotypes stay codes, one byte each
+    pub fn into_matrices(self) -> (Genotypes, Matrix) {
+        ((self.codes, self.num_snps), (self.phenotype, 1))
+    }
+}
+
+// how fields are separated in a text dataset. with Whitespace any run
+// of spaces and tabs separates fields
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Delimiter {
+    Whitespace,
+    Comma,
+    Tab,
+    Space,
+}
+
+pub const DELIMITER_NAMES: &[&str] = &["whitespace", "comma", "tab", "space"];
+
+impl Delimiter {
+    pub fn from_name(name: &str) -> Option<Delimiter> {
+        match name {
+            "whitespace" => Some(Delimiter::Whitespace),
+            "comma" => Some(Delimiter::Comma),
+            "tab" => Some(Delimiter::Tab),
+            "space" => Some(Delimiter::Space),
+            _ => None,
+        }
+    }
+
+    // the fields of a line, trimmed
+    pub fn split<'a>(&self, line: &'a str) -> Vec<&'a str> {
+        let sep: char = match self {
+            Delimiter::Whitespace => return line.split_whitespace().collect(),
+            Delimiter::Comma => ',',
+            Delimiter::Tab => '\t',
+            Delimiter::Space => ' ',
+        };
+
+        line.trim_end_matches(['\n', '\r'])
+            .split(sep)
+            .map(|field| field.trim())
+            .collect()
+    }
+}
+
+// how to read the columns of a text dataset. columns are given by
+// header name or 1-based column number
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadOptions {
+    pub delimiter: Delimiter,
+    // None detects a header: a first line with a field, other than the
+    // sample ID columns, that isn't a number or a missing token
+    pub has_header: Option<bool>,
+    // the last column if None
+    pub phenotype_col: Option<String>,
+    // skipped as genotypes. the first one is the sample ID, if there
+    // are more they are joined with _
+    pub sample_id_cols: Vec<String>,
+}
+
+impl Default for LoadOptions {
+    fn default() -> LoadOptions {
+        LoadOptions {
+            delimiter: Delimiter::Whitespace,
+            has_header: None,
+            phenotype_col: None,
+            sample_id_cols: Vec::new(),
+        }
+    }
+}
+
+impl LoadOptions {
+    // identifies the options in a binary dataset's source stamp, a
+    // cache made with different options is out of date
+    pub fn fingerprint(&self) -> u64 {
+        fnv1a_hash(format!("{:?}", self).as_bytes())
+    }
+
+    // the column a name or 1-based number refers to
+    fn find_column(&self, spec: &str, names: Option<&[String]>, n_fields: usize) -> Option<usize> {
+        if let Some(idx) = names.and_then(|names| names.iter().position(|n| n == spec)) {
+            return Some(idx);
+        }
+
+        spec.parse::<usize>()
+            .ok()
+            .filter(|num| (1..=n_fields).contains(num))
+            .map(|num| num - 1)
+    }
+
+    fn looks_like_header(&self, fields: &[&str]) -> bool {
+        let is_number = |spec: &String| spec.parse::<usize>().is_ok();
+
+        // columns named rather than numbered need a header
+        if !self.sample_id_cols.iter().all(is_number) || !self.phenotype_col.iter().all(is_number) {
+            return true;
+        }
+
+        fields.iter().enumerate().any(|(idx, field)| {
+            !self.sample_id_cols.contains(&(idx + 1).to_string())
+                && !MISSING_TOKENS.contains(field)
+                && field.parse::<f64>().is_err()
+        })
+    }
+}
+
+// where the phenotype, sample IDs and genotypes are in each line
+struct ColumnLayout {
+    n_fields: usize,
+    phenotype: usize,
+    id_cols: Vec<usize>,
+    snp_cols: Vec<usize>,
+}
+
+impl ColumnLayout {
+    fn new(
+        options: &LoadOptions,
+        names: Option<&[String]>,
+        n_fields: usize,
+    ) -> Result<ColumnLayout> {
+        let find = |param: &str, spec: &str| {
+            options.find_column(spec, names, n_fields).ok_or_else(|| {
+                let expected: &str = if names.is_some() {
+                    "a column name or number"
+                } else {
+                    "a column number, the dataset has no header"
+                };
+                AntsnapError::invalid_param(param, &format!("'{}' is not {}", spec, expected))
+            })
+        };
+
+        let phenotype: usize = match options.phenotype_col.as_ref() {
+            Some(spec) => find("phenotype_col", spec)?,
+            None => n_fields - 1,
+        };
+
+        let id_cols: Vec<usize> = options
+            .sample_id_cols
+            .iter()
+            .map(|spec| find("sample_id_cols", spec))
+            .collect::<Result<Vec<usize>>>()?;
+
+        if id_cols.contains(&phenotype) {
+            return Err(AntsnapError::invalid_param(
+                "sample_id_cols",
+                "includes the phenotype column",
+            ));
+        }
+
+        let snp_cols: Vec<usize> = (0..n_fields)
+            .filter(|col| *col != phenotype && !id_cols.contains(col))
+            .collect();
+
+        Ok(ColumnLayout {
+            n_fields,
+            phenotype,
+            id_cols,
+            snp_cols,
+        })
+    }
+
+    // SNP names followed by the phenotype name. a dataset without a
+    // header gets default names
+    fn header(&self, names: Option<&[String]>) -> Vec<String> {
+        match names {
+            Some(names) => self
+                .snp_cols
+                .iter()
+                .chain(std::iter::once(&self.phenotype))
+                .map(|col| names[*col].to_owned())
+                .collect(),
+            None => (0..self.snp_cols.len())
+                .map(default_snp_name)
+                .chain(std::iter::once("Class".to_owned()))
+                .collect(),
+        }
+    }
+}
+
+// name of the SNP in column idx, for files without a header
+pub fn default_snp_name(idx: usize) -> String {
+    format!("snp{}", idx + 1)
+}
+
+// parses a text dataset a line at a time into a GenotypeStore, so the
+// file is never held in memory. on_progress is called every
+// PROGRESS_ROWS rows and once at the end
+pub fn stream_data<F>(
+    fp: &str,
+    options: &LoadOptions,
+    mut on_progress: F,
+) -> Result<(GenotypeStore, Vec<String>)>
+where
+    F: FnMut(&LoadProgress),
+{
+    let io_err = |why| AntsnapError::io(fp, why);
+    let file = File::open(fp).map_err(io_err)?;
+    let total_bytes: u64 = file.metadata().map_err(io_err)?.len();
+    let mut reader = BufReader::new(file);
+
+    let mut store = GenotypeStore::default();
+    let mut header: Vec<String> = Vec::new();
+    let mut layout: Option<ColumnLayout> = None;
+    let mut progress = LoadProgress {
+        rows: 0,
+        bytes_read: 0,
+        total_bytes,
+    };
+    let mut next_report: usize = PROGRESS_ROWS;
+
+    let mut line = String::new();
+    let mut line_num: usize = 0;
+
+    loop {
+        line.clear();
+        let n_read: usize = reader.read_line(&mut line).map_err(io_err)?;
+        if n_read == 0 {
+            break;
+        }
+        line_num += 1;
+        progress.bytes_read += n_read as u64;
+
+        let data_err = |column: Option<usize>, msg: String| AntsnapError::Data {
+            path: fp.to_owned(),
+            line: line_num,
+            column,
+            msg,
+        };
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let fields: Vec<&str> = options.delimiter.split(&line);
+
+        // the first line decides the columns
+        if layout.is_none() {
+            let is_header: bool = options
+                .has_header
+                .unwrap_or_else(|| options.looks_like_header(&fields));
+            let names: Option<Vec<String>> = if is_header {
+                Some(fields.iter().map(|f| f.to_string()).collect())
+            } else {
+                None
+            };
+
+            let new_layout = ColumnLayout::new(options, names.as_deref(), fields.len())?;
+            if new_layout.snp_cols.is_empty() {
+                return Err(data_err(
+                    None,
+                    "expected at least one genotype and a class column".to_owned(),
+                ));
+            }
+
+            header = new_layout.header(names.as_deref());
+            store.num_snps = new_layout.snp_cols.len();
+            layout = Some(new_layout);
+
+            if is_header {
+                continue;
+            }
+        }
+        let cols: &ColumnLayout = layout.as_ref().unwrap();
+
+        if fields.len() != cols.n_fields {
+            return Err(data_err(
+                None,
+                format!("expected {} columns, found {}", cols.n_fields, fields.len()),
+            ));
+        }
+
+        for col_idx in cols.snp_cols.iter() {
+            let val: &str = fields[*col_idx];
+            if MISSING_TOKENS.contains(&val) {
+                store.codes.push(MISSING_CODE);
+                continue;
+            }
+
+            let parsed: f64 = val.parse::<f64>().map_err(|why| {
+                data_err(
+                    Some(col_idx + 1),
+                    format!("could not parse '{}' to f64: {}", val, why),
+                )
+            })?;
+            let code: u8 = genotype_code(parsed).ok_or_else(|| {
+                data_err(
+                    Some(col_idx + 1),
+                    format!("genotype '{}' is not 0, 1 or 2", val),
+                )
+            })?;
+            store.codes.push(code);
+        }
+
+        let val: &str = fields[cols.phenotype];
+        let missing_phenotype = || {
+            data_err(
+                Some(cols.phenotype + 1),
+                format!("missing phenotype '{}', remove samples without one", val),
+            )
+        };
+        if MISSING_TOKENS.contains(&val) {
+            return Err(missing_phenotype());
+        }
+        let parsed: f64 = val.parse::<f64>().map_err(|why| {
+            data_err(
+                Some(cols.phenotype + 1),
+                format!("could not parse '{}' to f64: {}", val, why),
+            )
+        })?;
+        if parsed < 0.0 {
+            return Err(missing_phenotype());
+        }
+        // anything else would be counted as a control
+        if parsed != 0.0 && parsed != 1.0 {
+            return Err(data_err(
+                Some(cols.phenotype + 1),
+                format!("phenotype '{}' is not 0 or 1", val),
+            ));
+        }
+        store.phenotype.push(parsed);
+
+        let sample_id: String = if cols.id_cols.is_empty() {
+            default_sample_id(store.sample_ids.len())
+        } else {
+            let ids: Vec<&str> = cols.id_cols.iter().map(|col| fields[*col]).collect();
+            ids.join("_")
+        };
+        store.sample_ids.push(sample_id);
+
+        progress.rows += 1;
+        // the last line is reported after the loop
+        if progress.rows >= next_report && progress.bytes_read < total_bytes {
+            on_progress(&progress);
+            next_report += PROGRESS_ROWS;
+        }
+    }
+
+    on_progress(&progress);
+
+    Ok((store, header))
+}
+
+// size and modification time of the file a binary dataset was made
+// from and the options it was read with, used to tell when a cache is
+// out of date. zero if there was no source file
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SourceStamp {
+    pub size: u64,
+    pub modified_nanos: u64,
+    pub options: u64,
+}
+
+pub fn source_stamp(fp: &str, options: &LoadOptions) -> Result<SourceStamp> {
+    let meta = fs::metadata(fp).map_err(|why| AntsnapError::io(fp, why))?;
+    let modified_nanos: u64 = meta
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |since| since.as_nanos() as u64);
+
+    Ok(SourceStamp {
+        size: meta.len(),
+        modified_nanos,
+        options: options.fingerprint(),
+    })
+}
+
+// passes writes through to inner, hashing everything written with
+// FNV-1a for the checksum at the end of a binary dataset
+struct ChecksumWriter<W: Write> {
+    inner: W,
+    hash: u64,
+}
+
+impl<W: Write> Write for ChecksumWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n_written: usize = self.inner.write(buf)?;
+        self.hash = fnv1a_update(self.hash, &buf[..n_written]);
+
+        Ok(n_written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+// writes the store and header as a binary dataset. numbers are little
+// endian, strings are a u32 length and UTF-8 bytes:
+//   MAGIC, format version (u32)
+//   number of samples, SNPs and header names (u64)
+//   source size, modification time in nanoseconds and options
+//   fingerprint (u64)
+//   header names, sample IDs
+//   phenotypes (f64), genotype codes (u8, row major)
+//   FNV-1a checksum of everything before it (u64)
+pub fn write_store(
+    store: &GenotypeStore,
+    header: &[String],
+    source: SourceStamp,
+    fp: &str,
+) -> Result<()> {
+    let io_err = |why| AntsnapError::io(fp, why);
+    let mut writer = ChecksumWriter {
+        inner: BufWriter::new(File::create(fp).map_err(io_err)?),
+        hash: FNV_OFFSET,
+    };
+
+    writer.write_all(MAGIC).map_err(io_err)?;
+    writer
+        .write_all(&FORMAT_VERSION.to_le_bytes())
+        .map_err(io_err)?;
+
+    let counts: [u64; 6] = [
+        store.num_samples() as u64,
+        store.num_snps as u64,
+        header.len() as u64,
+        source.size,
+        source.modified_nanos,
+        source.options,
+    ];
+    for count in counts.iter() {
+        writer.write_all(&count.to_le_bytes()).map_err(io_err)?;
+    }
+
+    for name in header.iter().chain(store.sample_ids.iter()) {
+        writer
+            .write_all(&(name.len() as u32).to_le_bytes())
+            .map_err(io_err)?;
+        writer.write_all(name.as_bytes()).map_err(io_err)?;
+    }
+
+    for val in store.phenotype.iter() {
+        writer.write_all(&val.to_le_bytes()).map_err(io_err)?;
+    }
+
+    writer.write_all(&store.codes).map_err(io_err)?;
+
+    let checksum: u64 = writer.hash;
+    writer
+        .inner
+        .write_all(&checksum.to_le_bytes())
+        .map_err(io_err)?;
+
+    writer.flush().map_err(io_err)
+}
+
+// a binary dataset mapped into memory. genotypes are read straight from
+// the mapping, only the names and IDs are copied out
+pub struct MappedStore {
+    mmap: Mmap,
+    pub header: Vec<String>,
+    pub sample_ids: Vec<String>,
+    pub source: SourceStamp,
+    pub num_samples: usize,
+    pub num_snps: usize,
+    phenotype_offset: usize,
+    codes_offset: usize,
+}
+
+// reads binary datasets written by write_store
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+    fp: &'a str,
+}
+
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        if self.bytes.len() - self.offset < n {
+            return Err(AntsnapError::Cache {
+                path: self.fp.to_owned(),
+                msg: "file is truncated".to_owned(),
+            });
+        }
+
+        let taken: &'a [u8] = &self.bytes[self.offset..self.offset + n];
+        self.offset += n;
+
+        Ok(taken)
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let len: usize = self.u32()? as usize;
+
+        String::from_utf8(self.take(len)?.to_vec()).map_err(|_| AntsnapError::Cache {
+            path: self.fp.to_owned(),
+            msg: "a name is not valid UTF-8".to_owned(),
+        })
+    }
+}
+
+// whether fp starts with the binary dataset magic bytes
+pub fn is_binary_dataset(fp: &str) -> bool {
+    let mut magic: [u8; 8] = [0; 8];
+
+    File::open(fp)
+        .and_then(|mut file| file.read_exact(&mut magic))
+        .map(|_| &magic == MAGIC)
+        .unwrap_or(false)
+}
+
+impl MappedStore {
+    // maps fp and checks its checksum
+    pub fn open(fp: &str) -> Result<MappedStore> {
+        let io_err = |why| AntsnapError::io(fp, why);
+        let cache_err = |msg: &str| AntsnapError::Cache {
+            path: fp.to_owned(),
+            msg: msg.to_owned(),
+        };
+
+        if !Path::new(fp).is_file() || !is_binary_dataset(fp) {
+            return Err(cache_err("not an antsnap binary dataset"));
+        }
+
+        let file = File::open(fp).map_err(io_err)?;
+        // the mapping is read only. the file being changed while it is
+        // mapped is not guarded against, same as any other reader
+        let mmap: Mmap = unsafe { Mmap::map(&file) }.map_err(io_err)?;
+
+        if mmap.len() < MAGIC.len() + 12 {
+            return Err(cache_err("file is truncated"));
+        }
+
+        let mut reader = ByteReader {
+            bytes: &mmap[..mmap.len() - 8],
+            offset: MAGIC.len(),
+            fp,
+        };
+
+        let version: u32 = reader.u32()?;
+        if version != FORMAT_VERSION {
+            return Err(cache_err(&format!(
+                "format version {} is not supported, expected {}",
+                version, FORMAT_VERSION
+            )));
+        }
+
+        let stored_checksum: u64 = u64::from_le_bytes(mmap[mmap.len() - 8..].try_into().unwrap());
+        if fnv1a_hash(reader.bytes) != stored_checksum {
+            return Err(cache_err("checksum does not match, the file is corrupt"));
+        }
+
+        let too_large = || cache_err("header has impossible dimensions");
+        let n_bytes: u64 = reader.bytes.len() as u64;
+        let as_len = |val: u64| {
+            if val > n_bytes {
+                Err(too_large())
+            } else {
+                Ok(val as usize)
+            }
+        };
+
+        let num_samples: usize = as_len(reader.u64()?)?;
+        let num_snps: usize = as_len(reader.u64()?)?;
+        let num_names: usize = as_len(reader.u64()?)?;
+        let source = SourceStamp {
+            size: reader.u64()?,
+            modified_nanos: reader.u64()?,
+            options: reader.u64()?,
+        };
+
+        let header: Vec<String> = (0..num_names)
+            .map(|_| reader.string())
+            .collect::<Result<Vec<String>>>()?;
+        let sample_ids: Vec<String> = (0..num_samples)
+            .map(|_| reader.string())
+            .collect::<Result<Vec<String>>>()?;
+
+        let phenotype_offset: usize = reader.offset;
+        reader.take(num_samples.checked_mul(8).ok_or_else(too_large)?)?;
+        let codes_offset: usize = reader.offset;
+        reader.take(num_samples.checked_mul(num_snps).ok_or_else(too_large)?)?;
+
+        if reader.offset != reader.bytes.len() {
+            return Err(cache_err("file is longer than its header says"));
+        }
+
+        Ok(MappedStore {
+            header,
+            sample_ids,
+            source,
+            num_samples,
+            num_snps,
+            phenotype_offset,
+            codes_offset,
+            mmap,
+        })
+    }
+
+    pub fn phenotype(&self, row: usize) -> f64 {
+        let start: usize = self.phenotype_offset + row * 8;
+        f64::from_le_bytes(self.mmap[start..start + 8].try_into().unwrap())
+    }
+
+    // the genotype codes of a sample
+    pub fn row(&self, row: usize) -> &[u8] {
+        let start: usize = self.codes_offset + row * self.num_snps;
+        &self.mmap[start..start + self.num_snps]
+    }
+
+    fn codes(&self) -> &[u8] {
+        &self.mmap[self.codes_offset..self.codes_offset + self.num_samples * self.num_snps]
+    }
+
+    pub fn to_matrices(&self) -> (Genotypes, Matrix) {
+        let x: Genotypes = (self.codes().to_vec(), self.num_snps);
+        let y: Matrix = (
+            (0..self.num_samples)
+                .map(|row| self.phenotype(row))
+                .collect(),
+            1,
+        );
+
+        (x, y)
+    }
+
+    // copies the data out of the mapping
+    pub fn to_store(&self) -> GenotypeStore {
+        GenotypeStore {
+            codes: self.codes().to_vec(),
+            num_snps: self.num_snps,
+            phenotype: (0..self.num_samples)
+                .map(|row| self.phenotype(row))
+                .collect(),
+            sample_ids: self.sample_ids.to_owned(),
+        }
+    }
+}
+
+// loads a text or binary dataset, whichever fp is. options and
+// on_progress only apply to text. the genotypes are kept as one byte
+// codes, see GenotypeStore
+pub fn load_data_with_progress<F>(
+    fp: &str,
+    options: &LoadOptions,
+    on_progress: F,
+) -> Result<(Genotypes, Matrix, Vec<String>)>
+where
+    F: FnMut(&LoadProgress),
+{
+    if is_binary_dataset(fp) {
+        let mapped: MappedStore = MappedStore::open(fp)?;
+        let (x, y) = mapped.to_matrices();
+
+        return Ok((x, y, mapped.header));
+    }
+
+    let (store, header) = stream_data(fp, options, on_progress)?;
+    let (x, y) = store.into_matrices();
+
+    Ok((x, y, header))
+}
+
+// loads a text or binary dataset as a GenotypeStore
+pub fn load_store<F>(
+    fp: &str,
+    options: &LoadOptions,
+    on_progress: F,
+) -> Result<(GenotypeStore, Vec<String>)>
+where
+    F: FnMut(&LoadProgress),
+{
+    if is_binary_dataset(fp) {
+        let mapped: MappedStore = MappedStore::open(fp)?;
+
+        return Ok((mapped.to_store(), mapped.header));
+    }
+
+    stream_data(fp, options, on_progress)
+}
+
+// loads fp through the binary cache at cache_fp. the cache is read if it
+// is intact and was made from fp as it is now with the same options,
+// otherwise fp is parsed and the cache rewritten. if fp no longer exists
+// an intact cache made with the same options is used
+pub fn load_data_cached<F>(
+    fp: &str,
+    cache_fp: &str,
+    options: &LoadOptions,
+    on_progress: F,
+) -> Result<(Genotypes, Matrix, Vec<String>)>
+where
+    F: FnMut(&LoadProgress),
+{
+    if is_binary_dataset(fp) {
+        return load_data_with_progress(fp, options, on_progress);
+    }
+
+    let source: Option<SourceStamp> = source_stamp(fp, options).ok();
+
+    if let Ok(mapped) = MappedStore::open(cache_fp) {
+        let is_current: bool = match source {
+            Some(stamp) => stamp == mapped.source,
+            None => mapped.source.options == options.fingerprint(),
+        };
+
+        if is_current {
+            let (x, y) = mapped.to_matrices();
+
+            return Ok((x, y, mapped.header));
+        }
+    }
+
+    let (store, header) = stream_data(fp, options, on_progress)?;
+    write_store(&store, &header, source.unwrap_or_default(), cache_fp)?;
+    let (x, y) = store.into_matrices();
+
+    Ok((x, y, header))
+}
@@ -0,0 +1,334 @@
+use rand::prelude::*;
+use rand_pcg::Pcg32;
+use std::cmp::Ordering;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::BufWriter;
+
+use crate::benchmark::*;
+use crate::config::*;
+use crate::error::*;
+
+// values tried for each tuned parameter. a parameter with no values
+// keeps its value from the base parameters
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SweepSpace {
+    pub t_0: Vec<f64>,
+    pub evap_coeff: Vec<f64>,
+    pub lambda: Vec<f64>,
+    pub num_ants: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SearchStrategy {
+    // every combination on every replicate
+    Grid,
+    // this many combinations chosen at random, on every replicate
+    Random(usize),
+    // successive halving with this reduction factor. every combination
+    // runs on a few replicates, the best 1/eta go on to eta times as
+    // many, until one is left or the replicates run out
+    Halving(usize),
+}
+
+// a parameter setting and how it did
+#[derive(Debug, Clone)]
+pub struct SweepResult {
+    pub params: ResolvedParams,
+    pub results: Vec<ReplicateResult>,
+    pub summary: BenchmarkSummary,
+}
+
+// parses a list of values, either comma separated or as start:end:count
+// for count evenly spaced values from start to end
+pub fn parse_sweep_values(name: &str, spec: &str) -> Result<Vec<f64>> {
+    let invalid = |msg: &str| AntsnapError::invalid_param(name, &format!("'{}' {}", spec, msg));
+
+    let parse = |val: &str| {
+        val.trim()
+            .parse::<f64>()
+            .map_err(|_| invalid("is not a list of numbers or a start:end:count range"))
+    };
+
+    if spec.contains(':') {
+        let parts: Vec<&str> = spec.split(':').collect();
+        if parts.len() != 3 {
+            return Err(invalid("is not a start:end:count range"));
+        }
+
+        let start: f64 = parse(parts[0])?;
+        let end: f64 = parse(parts[1])?;
+        let count: usize = parts[2]
+            .trim()
+            .parse::<usize>()
+            .map_err(|_| invalid("has a count that is not a whole number"))?;
+
+        if count < 2 {
+            return Err(invalid("must have a count of at least 2"));
+        }
+
+        let step: f64 = (end - start) / (count - 1) as f64;
+
+        Ok((0..count).map(|idx| start + step * idx as f64).collect())
+    } else {
+        spec.split(',').map(parse).collect()
+    }
+}
+
+// parses values for a whole number parameter, ranges are rounded and
+// repeated values dropped
+pub fn parse_sweep_counts(name: &str, spec: &str) -> Result<Vec<usize>> {
+    let vals: Vec<f64> = parse_sweep_values(name, spec)?;
+
+    if vals.iter().any(|v| *v < 0.0) {
+        return Err(AntsnapError::invalid_param(
+            name,
+            &format!("'{}' has a negative value", spec),
+        ));
+    }
+
+    let mut counts: Vec<usize> = vals.iter().map(|v| v.round() as usize).collect();
+    counts.dedup();
+
+    Ok(counts)
+}
+
+impl SweepSpace {
+    // every combination of the values, applied to base
+    pub fn grid(&self, base: &ResolvedParams) -> Vec<ResolvedParams> {
+        let or_base = |vals: &[f64], base_val: f64| {
+            if vals.is_empty() {
+                vec![base_val]
+            } else {
+                vals.to_vec()
+            }
+        };
+
+        let num_ants: Vec<usize> = if self.num_ants.is_empty() {
+            vec![base.num_ants]
+        } else {
+            self.num_ants.to_owned()
+        };
+
+        let mut grid: Vec<ResolvedParams> = Vec::new();
+
+        for t_0 in or_base(&self.t_0, base.t_0).iter() {
+            for evap_coeff in or_base(&self.evap_coeff, base.evap_coeff).iter() {
+                for lambda in or_base(&self.lambda, base.lambda).iter() {
+                    for n_ants in num_ants.iter() {
+                        let mut params: ResolvedParams = base.to_owned();
+                        params.t_0 = *t_0;
+                        params.evap_coeff = *evap_coeff;
+                        params.lambda = *lambda;
+                        params.num_ants = *n_ants;
+                        grid.push(params);
+                    }
+                }
+            }
+        }
+
+        grid
+    }
+}
+
+// the swept parameters of a setting, for progress output
+pub fn describe_setting(params: &ResolvedParams) -> String {
+    format!(
+        "t_0={} evap_coeff={} lambda={} num_ants={}",
+        params.t_0, params.evap_coeff, params.lambda, params.num_ants
+    )
+}
+
+// orders summaries best first, by top-1 power, then top-N power, then
+// mean rank and finally runtime
+pub fn compare_summaries(a: &BenchmarkSummary, b: &BenchmarkSummary) -> Ordering {
+    let rank = |s: &BenchmarkSummary| s.mean_rank.unwrap_or(f64::INFINITY);
+
+    b.top_1_power
+        .total_cmp(&a.top_1_power)
+        .then(b.top_n_power.total_cmp(&a.top_n_power))
+        .then(rank(a).total_cmp(&rank(b)))
+        .then(a.mean_runtime_secs.total_cmp(&b.mean_runtime_secs))
+}
+
+// runs the alive settings on replicates up to n_reps. each replicate is
+// loaded once for all of the settings
+fn evaluate<F>(
+    data: &BenchmarkData,
+    candidates: &[ResolvedParams],
+    alive: &[usize],
+    results: &mut [Vec<ReplicateResult>],
+    n_reps: usize,
+    on_result: &mut F,
+) -> Result<()>
+where
+    F: FnMut(&ResolvedParams, &ReplicateResult),
+{
+    let done: usize = alive
+        .iter()
+        .map(|c| results[*c].len())
+        .min()
+        .unwrap_or(n_reps);
+
+    for rep in done..n_reps {
+        let (name, x, y, header) = data.load(rep)?;
+
+        for c in alive.iter() {
+            if results[*c].len() > rep {
+                continue;
+            }
+
+            let result: ReplicateResult = benchmark_replicate(
+                &name,
+                x.to_owned(),
+                y.to_owned(),
+                header.to_owned(),
+                &candidates[*c],
+            )?;
+
+            on_result(&candidates[*c], &result);
+            results[*c].push(result);
+        }
+    }
+
+    Ok(())
+}
+
+// runs the settings in space over the replicates and returns them best
+// first. settings dropped by successive halving are reported with the
+// replicates they ran on. seed is used to choose settings for random
+// search
+pub fn run_sweep<F>(
+    data: &BenchmarkData,
+    base: &ResolvedParams,
+    space: &SweepSpace,
+    strategy: SearchStrategy,
+    top_n: usize,
+    seed: Option<u64>,
+    mut on_result: F,
+) -> Result<Vec<SweepResult>>
+where
+    F: FnMut(&ResolvedParams, &ReplicateResult),
+{
+    let mut candidates: Vec<ResolvedParams> = space.grid(base);
+
+    for params in candidates.iter() {
+        params.validate()?;
+    }
+
+    if let SearchStrategy::Random(n_samples) = strategy {
+        let mut rng: Pcg32 = match seed {
+            Some(seed) => Pcg32::seed_from_u64(seed),
+            None => Pcg32::from_entropy(),
+        };
+        candidates = candidates
+            .choose_multiple(&mut rng, n_samples)
+            .cloned()
+            .collect();
+    }
+
+    let n_replicates: usize = data.len();
+    let mut results: Vec<Vec<ReplicateResult>> = vec![Vec::new(); candidates.len()];
+    let mut alive: Vec<usize> = (0..candidates.len()).collect();
+
+    match strategy {
+        SearchStrategy::Grid | SearchStrategy::Random(_) => {
+            evaluate(
+                data,
+                &candidates,
+                &alive,
+                &mut results,
+                n_replicates,
+                &mut on_result,
+            )?;
+        }
+        SearchStrategy::Halving(eta) => {
+            if eta < 2 {
+                return Err(AntsnapError::invalid_param("eta", "must be at least 2"));
+            }
+
+            // start small enough that the last round can use every
+            // replicate
+            let mut n_rounds: u32 = 0;
+            while eta.pow(n_rounds) < alive.len() {
+                n_rounds += 1;
+            }
+            let mut n_reps: usize = (n_replicates / eta.pow(n_rounds)).max(1);
+
+            loop {
+                evaluate(
+                    data,
+                    &candidates,
+                    &alive,
+                    &mut results,
+                    n_reps,
+                    &mut on_result,
+                )?;
+
+                if alive.len() == 1 || n_reps >= n_replicates {
+                    break;
+                }
+
+                alive.sort_by(|a, b| {
+                    compare_summaries(
+                        &summarize(&results[*a], top_n),
+                        &summarize(&results[*b], top_n),
+                    )
+                });
+                alive.truncate((alive.len() / eta).max(1));
+
+                n_reps = (n_reps * eta).min(n_replicates);
+            }
+        }
+    }
+
+    let mut sweep: Vec<SweepResult> = candidates
+        .into_iter()
+        .zip(results)
+        .map(|(params, results)| SweepResult {
+            summary: summarize(&results, top_n),
+            params,
+            results,
+        })
+        .collect();
+
+    // settings that ran on more replicates survived more rounds
+    sweep.sort_by(|a, b| {
+        b.results
+            .len()
+            .cmp(&a.results.len())
+            .then(compare_summaries(&a.summary, &b.summary))
+    });
+
+    Ok(sweep)
+}
+
+// writes one row per setting as a TSV, best first
+pub fn write_sweep(sweep: &[SweepResult], fp: &str) -> Result<()> {
+    let io_err = |why| AntsnapError::io(fp, why);
+    let mut writer = BufWriter::new(File::create(fp).map_err(io_err)?);
+
+    writeln!(
+        writer,
+        "t_0\tevap_coeff\tlambda\tnum_ants\t{}",
+        SUMMARY_FIELDS_HEADER
+    )
+    .map_err(io_err)?;
+
+    for setting in sweep.iter() {
+        writeln!(writer, "{}", sweep_row(setting)).map_err(io_err)?;
+    }
+
+    writer.flush().map_err(io_err)
+}
+
+pub fn sweep_row(setting: &SweepResult) -> String {
+    format!(
+        "{}\t{}\t{}\t{}\t{}",
+        setting.params.t_0,
+        setting.params.evap_coeff,
+        setting.params.lambda,
+        setting.params.num_ants,
+        summary_fields(&setting.summary)
+    )
+}
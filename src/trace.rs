@@ -0,0 +1,154 @@
+use serde::Serialize;
+use std::fs::{self, File, OpenOptions};
+use std::io::prelude::*;
+use std::io::BufWriter;
+use std::path::Path;
+
+use crate::error::*;
+
+// summary of a single iteration of the colony
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct IterationStats {
+    pub iter: usize,
+    pub best_score: f64,
+    pub mean_score: f64,
+    pub median_score: f64,
+    pub distinct_paths: usize,
+    pub pheromone_min: f64,
+    pub pheromone_max: f64,
+    pub pheromone_entropy: f64,
+    pub elite_paths: usize,
+    pub time_secs: f64,
+    // rank of the ground truth SNP set among the distinct sets built
+    // this iteration, if it was built at all
+    pub true_rank: Option<usize>,
+}
+
+const CSV_HEADER: &str = "iter,best_score,mean_score,median_score,distinct_paths,\
+pheromone_min,pheromone_max,pheromone_entropy,elite_paths,time_secs,true_rank";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TraceFormat {
+    Csv,
+    Jsonl,
+}
+
+impl TraceFormat {
+    // the format is chosen from the file extension, CSV unless the
+    // extension is .jsonl or .json
+    pub fn from_path(fp: &str) -> TraceFormat {
+        if fp.ends_with(".jsonl") || fp.ends_with(".json") {
+            TraceFormat::Jsonl
+        } else {
+            TraceFormat::Csv
+        }
+    }
+}
+
+// writes one record per iteration, flushing after each so the trace
+// of a long run can be watched while it is in progress
+pub struct TraceWriter {
+    writer: BufWriter<File>,
+    format: TraceFormat,
+    fp: String,
+}
+
+impl TraceWriter {
+    pub fn new(fp: &str) -> Result<TraceWriter> {
+        let file = File::create(fp).map_err(|why| AntsnapError::io(fp, why))?;
+
+        let mut trace_writer = TraceWriter {
+            writer: BufWriter::new(file),
+            format: TraceFormat::from_path(fp),
+            fp: fp.to_owned(),
+        };
+
+        if trace_writer.format == TraceFormat::Csv {
+            trace_writer.write_line(CSV_HEADER.to_owned())?;
+        }
+
+        Ok(trace_writer)
+    }
+
+    // continues the trace of a run resumed at iteration from_iter. rows
+    // for from_iter on, written after the checkpoint and before the run
+    // stopped, are dropped as those iterations run again. a missing trace
+    // is started over
+    pub fn resume(fp: &str, from_iter: usize) -> Result<TraceWriter> {
+        if !Path::new(fp).exists() {
+            return TraceWriter::new(fp);
+        }
+
+        let io_err = |why| AntsnapError::io(fp, why);
+        let format: TraceFormat = TraceFormat::from_path(fp);
+
+        let contents: String = fs::read_to_string(fp).map_err(io_err)?;
+        let kept: String = contents
+            .lines()
+            .filter(|line| match row_iter(line, format) {
+                Some(iter) => iter < from_iter,
+                // the CSV header
+                None => true,
+            })
+            .map(|line| format!("{}\n", line))
+            .collect();
+        fs::write(fp, kept).map_err(io_err)?;
+
+        let file = OpenOptions::new().append(true).open(fp).map_err(io_err)?;
+
+        Ok(TraceWriter {
+            writer: BufWriter::new(file),
+            format,
+            fp: fp.to_owned(),
+        })
+    }
+
+    pub fn write(&mut self, stats: &IterationStats) -> Result<()> {
+        let line = match self.format {
+            TraceFormat::Csv => to_csv_row(stats),
+            TraceFormat::Jsonl => serde_json::to_string(stats).unwrap(),
+        };
+
+        self.write_line(line)
+    }
+
+    fn write_line(&mut self, line: String) -> Result<()> {
+        writeln!(self.writer, "{}", line)
+            .and_then(|_| self.writer.flush())
+            .map_err(|why| AntsnapError::io(&self.fp, why))
+    }
+}
+
+// the iteration a trace line is for, None for the CSV header
+fn row_iter(line: &str, format: TraceFormat) -> Option<usize> {
+    match format {
+        TraceFormat::Csv => line.split(',').next()?.parse::<usize>().ok(),
+        TraceFormat::Jsonl => serde_json::from_str::<serde_json::Value>(line)
+            .ok()?
+            .get("iter")?
+            .as_u64()
+            .map(|iter| iter as usize),
+    }
+}
+
+pub fn to_csv_row(stats: &IterationStats) -> String {
+    let true_rank: String = match stats.true_rank {
+        Some(rank) => rank.to_string(),
+        None => String::new(),
+    };
+
+    format!(
+        "{},{},{},{},{},{},{},{},{},{},{}",
+        stats.iter,
+        stats.best_score,
+        stats.mean_score,
+        stats.median_score,
+        stats.distinct_paths,
+        stats.pheromone_min,
+        stats.pheromone_max,
+        stats.pheromone_entropy,
+        stats.elite_paths,
+        stats.time_secs,
+        true_rank
+    )
+}
@@ -1,12 +1,27 @@
+use std::cmp::Ordering;
 use std::fs::File;
 use std::io::prelude::*;
+use std::io::BufWriter;
 
 use rand::prelude::*;
 use rand::seq::SliceRandom;
 
+use crate::error::*;
+use crate::store::*;
+
 type SNP = usize;
 type Element = f64;
 type Matrix = (Vec<Element>, usize);
+type Genotypes = (Vec<u8>, usize);
+
+// missing genotypes are stored as this value
+pub const MISSING_GENOTYPE: f64 = -1.0;
+// tokens read as a missing genotype. PLINK, VCF and R style
+pub const MISSING_TOKENS: &[&str] = &["NA", "-9", ".", "./.", ".|."];
+
+pub fn is_missing(genotype: f64) -> bool {
+    genotype < 0.0
+}
 
 // threshold for comparing f64s
 const FP_EQUALITY_THRESH: f64 = 0.001;
@@ -33,12 +48,12 @@ pub fn get_r(i: &SNP, j: &SNP, pheromones: &Matrix, current_path: &Vec<SNP>) ->
 }
 
 // get a transfer probability for moving from SNP i to SNP j
-pub fn transfer_prob(
+pub fn transfer_prob<R: Rng>(
     i: &SNP,
     j: &SNP,
     pheromones: &Matrix,
     current_path: &Vec<SNP>,
-    rng: &mut ThreadRng,
+    rng: &mut R,
     threshold: f64,
 ) -> f64 {
     let mut prob_out: f64 = 0.0;
@@ -71,33 +86,57 @@ pub fn get_max(slice: &[f64]) -> f64 {
 }
 
 // expands a single path until the desired dimension
-pub fn expand_path(
+pub fn expand_path<R: Rng>(
     current_path: &mut Vec<SNP>,
     pheromones: &Matrix,
     epis_dim: usize,
     threshold: f64,
+    rng: &mut R,
 ) {
-    let mut rng = rand::thread_rng();
+    expand_path_excluding(current_path, pheromones, epis_dim, threshold, &[], rng);
+}
 
+// expands a single path, never adding a SNP that is in linked[snp] for
+// a SNP already in the path. linked can be empty, or shorter than the
+// number of SNPs
+pub fn expand_path_excluding<R: Rng>(
+    current_path: &mut Vec<SNP>,
+    pheromones: &Matrix,
+    epis_dim: usize,
+    threshold: f64,
+    linked: &[Vec<SNP>],
+    rng: &mut R,
+) {
     while current_path.len() < epis_dim {
-        add_to_path(current_path, pheromones, threshold, &mut rng);
+        add_to_path(current_path, pheromones, threshold, linked, rng);
     }
 }
 
-// add the next SNP to the path
-pub fn add_to_path(
+// add the next SNP to the path. SNPs linked to one already in the path
+// are skipped, unless every remaining SNP is
+pub fn add_to_path<R: Rng>(
     current_path: &mut Vec<SNP>,
     pheromones: &Matrix,
     threshold: f64,
-    rng: &mut ThreadRng,
+    linked: &[Vec<SNP>],
+    rng: &mut R,
 ) {
     let i: &SNP = current_path.last().unwrap();
 
+    let is_linked = |snp: &SNP| {
+        current_path
+            .iter()
+            .any(|member| linked.get(*member).is_some_and(|l| l.contains(snp)))
+    };
+    let all_linked: bool = (0..pheromones.1)
+        .filter(|snp| !current_path.contains(snp))
+        .all(|snp| is_linked(&snp));
+
     // get the probability of moving to all other SNPs
     let mut probs: (Vec<usize>, Vec<f64>) = (Vec::new(), Vec::new());
 
     for snp in 0..pheromones.1 {
-        if !current_path.contains(&snp) {
+        if !current_path.contains(&snp) && (all_linked || !is_linked(&snp)) {
             probs.1.push(transfer_prob(
                 i,
                 &snp,
@@ -125,12 +164,71 @@ pub fn add_to_path(
     current_path.push(snps_at_max.choose(rng).unwrap().to_owned());
 }
 
+// returns the canonical form of a path. a path is a set of SNPs, so
+// [1, 5, 9] and [9, 1, 5] are the same solution
+pub fn canonical_path(path: &Vec<SNP>) -> Vec<SNP> {
+    let mut path_out: Vec<SNP> = path.to_owned();
+    path_out.sort_unstable();
+
+    path_out
+}
+
+// advances comb, a sorted combination of values below n, to the next
+// combination in lexicographic order. returns false when comb was the
+// last one. an empty comb has no next combination
+pub fn next_combination(comb: &mut [usize], n: usize) -> bool {
+    let k: usize = comb.len();
+
+    for idx in (0..k).rev() {
+        if comb[idx] < n - k + idx {
+            comb[idx] += 1;
+            for next in (idx + 1)..k {
+                comb[next] = comb[next - 1] + 1;
+            }
+            return true;
+        }
+    }
+
+    false
+}
+
+// returns the columns of the named SNPs
+pub fn resolve_snp_names(header: &[String], names: &[String]) -> Result<Vec<SNP>> {
+    names
+        .iter()
+        .map(|name| {
+            header
+                .iter()
+                .position(|h| h == name)
+                .ok_or_else(|| AntsnapError::UnknownSnp(name.to_owned()))
+        })
+        .collect()
+}
+
+// label for a column of a contingency table built from k SNPs, the
+// genotype of each SNP separated by '/'
+pub fn genotype_label(col_idx: usize, k: usize) -> String {
+    let mut digits: Vec<String> = Vec::with_capacity(k);
+    let mut remainder: usize = col_idx;
+
+    for _ in 0..k {
+        digits.push((remainder % 3).to_string());
+        remainder /= 3;
+    }
+    digits.reverse();
+
+    digits.join("/")
+}
+
 // initialize ants with a random SNP
-pub fn init_ants(num_ants: usize, num_snps: usize, epis_dim: usize) -> Vec<Vec<SNP>> {
+pub fn init_ants<R: Rng>(
+    num_ants: usize,
+    num_snps: usize,
+    epis_dim: usize,
+    rng: &mut R,
+) -> Vec<Vec<SNP>> {
     let mut paths_out: Vec<Vec<SNP>> = Vec::with_capacity(epis_dim);
 
-    let mut rng = rand::thread_rng();
-
     for _ in 0..num_ants {
         let num: usize = rng.gen_range(0, num_snps);
         let path = vec![num];
@@ -154,6 +252,148 @@ pub fn init_pheromones(num_snps: usize) -> Matrix {
     matrix_out
 }
 
+// normalized Shannon entropy of the pheromone matrix, in [0, 1]. only
+// the upper triangle is used as the matrix is symmetric. this is 1.0
+// when all pheromones are equal and falls as they concentrate on
+// fewer edges
+pub fn pheromone_entropy(pheromones: &Matrix) -> f64 {
+    let n: usize = pheromones.1;
+    let mut vals: Vec<f64> = Vec::with_capacity(n * n / 2);
+
+    for row in 0..n {
+        for col in (row + 1)..n {
+            vals.push(pheromones.0.get(row * n + col).unwrap().to_owned());
+        }
+    }
+
+    let total: f64 = vals.iter().sum();
+
+    if vals.len() < 2 || total <= 0.0 {
+        return 0.0;
+    }
+
+    let mut entropy: f64 = 0.0;
+
+    for val in vals.iter() {
+        if *val > 0.0 {
+            let p = val / total;
+            entropy -= p * p.ln();
+        }
+    }
+
+    entropy / (vals.len() as f64).ln()
+}
+
+// min and max pheromone values, over the upper triangle as with
+// pheromone_entropy
+pub fn pheromone_range(pheromones: &Matrix) -> (f64, f64) {
+    let n: usize = pheromones.1;
+    let mut min: f64 = f64::INFINITY;
+    let mut max: f64 = f64::NEG_INFINITY;
+
+    for row in 0..n {
+        for col in (row + 1)..n {
+            let val: f64 = pheromones.0.get(row * n + col).unwrap().to_owned();
+            min = min.min(val);
+            max = max.max(val);
+        }
+    }
+
+    (min, max)
+}
+
+// orders scores best first. NaN sorts last, after every real score,
+// instead of panicking the sort
+pub fn best_first(a: f64, b: f64) -> Ordering {
+    match (a.is_nan(), b.is_nan()) {
+        (false, false) => b.total_cmp(&a),
+        (a_nan, b_nan) => a_nan.cmp(&b_nan),
+    }
+}
+
+// get the median of a slice of f64
+pub fn get_median(slice: &[f64]) -> f64 {
+    let mut sorted: Vec<f64> = slice.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+
+    let mid: usize = sorted.len() / 2;
+
+    if sorted.is_empty() {
+        0.0
+    } else if sorted.len() % 2 == 1 {
+        sorted.get(mid).unwrap().to_owned()
+    } else {
+        (sorted.get(mid - 1).unwrap() + sorted.get(mid).unwrap()) / 2.0
+    }
+}
+
+// returns the canonical path of the ground truth SNPs on simulated data.
+// these are either named explicitly or, following the GAMETES naming
+// convention, are the SNPs whose names start with M0P (or MOP)
+pub fn get_true_solution(header: &[String], true_snps: &Option<Vec<String>>) -> Option<Vec<SNP>> {
+    let path: Vec<SNP> = match true_snps {
+        Some(names) => {
+            let mut path: Vec<SNP> = Vec::new();
+            for name in names.iter() {
+                path.push(header.iter().position(|h| h == name)?);
+            }
+            path
+        }
+        None => header
+            .iter()
+            .enumerate()
+            .filter(|(_, h)| h.starts_with("M0P") || h.starts_with("MOP"))
+            .map(|(idx, _)| idx)
+            .collect(),
+    };
+
+    if path.is_empty() {
+        None
+    } else {
+        Some(canonical_path(&path))
+    }
+}
+
+// starting value of an FNV-1a hash
+pub const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+// 64-bit FNV-1a hash. used to fingerprint datasets and configs, so
+// unlike DefaultHasher it must be stable across builds
+pub fn fnv1a_hash(bytes: &[u8]) -> u64 {
+    fnv1a_update(FNV_OFFSET, bytes)
+}
+
+// continues hash over more bytes, for hashing data in pieces
+pub fn fnv1a_update(mut hash: u64, bytes: &[u8]) -> u64 {
+    for byte in bytes.iter() {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+
+    hash
+}
+
+// fingerprint of a loaded dataset, hashed a value at a time rather than
+// copying the dataset into one buffer
+pub fn hash_dataset(x: &Genotypes, y: &Matrix, header: &[String]) -> u64 {
+    let mut hash: u64 = fnv1a_hash(&(x.1 as u64).to_le_bytes());
+
+    // genotypes are hashed as values, so codes and f64s hash the same
+    for val in
+        x.0.iter()
+            .map(|code| code_genotype(*code))
+            .chain(y.0.iter().cloned())
+    {
+        hash = fnv1a_update(hash, &val.to_le_bytes());
+    }
+    for name in header.iter() {
+        hash = fnv1a_update(hash, name.as_bytes());
+        hash = fnv1a_update(hash, b"\t");
+    }
+
+    hash
+}
+
 // returns a m x 1 matrix for column j
 pub fn get_column(m: &Matrix, j: usize) -> Matrix {
     let mut m_out: Matrix = (Vec::new(), 1);
@@ -168,12 +408,15 @@ pub fn get_column(m: &Matrix, j: usize) -> Matrix {
 }
 
 // append b columns to a
-pub fn append_columns(a: &Matrix, b: &Matrix) -> Matrix {
+pub fn append_columns(a: &Matrix, b: &Matrix) -> Result<Matrix> {
     let a_n_rows: usize = a.0.len() / a.1;
     let b_n_rows: usize = b.0.len() / b.1;
 
     if a_n_rows != b_n_rows {
-        panic!("utils::append_columns - matrices do not have same number of rows!");
+        return Err(AntsnapError::Shape(format!(
+            "utils::append_columns - matrices do not have same number of rows ({} and {})",
+            a_n_rows, b_n_rows
+        )));
     }
 
     let mut m_out: Matrix = (Vec::new(), a.1 + b.1);
@@ -190,7 +433,7 @@ pub fn append_columns(a: &Matrix, b: &Matrix) -> Matrix {
         }
     }
 
-    m_out
+    Ok(m_out)
 }
 
 // transposes a matrix
@@ -207,74 +450,115 @@ pub fn transpose(m: &Matrix) -> Matrix {
 }
 
 // appends the rows in b to a
-pub fn append_rows(a: &mut Matrix, b: &Matrix) {
+pub fn append_rows(a: &mut Matrix, b: &Matrix) -> Result<()> {
     if a.1 != b.1 {
-        panic!("utils::append_rows - matrices do not have same dims");
+        return Err(AntsnapError::Shape(format!(
+            "utils::append_rows - matrices do not have same dims ({} and {} columns)",
+            a.1, b.1
+        )));
     }
 
     for val in b.0.iter() {
         a.0.push(val.to_owned());
     }
+
+    Ok(())
 }
 
 // returns a matrix that is a subset of the columns in m,
 // columns are designated by the indices in the cols Vec
-pub fn column_subset(m: &Matrix, cols: &Vec<usize>) -> Matrix {
-    let init_vals: Vec<Element> = get_column(m, cols.first().unwrap().to_owned()).0;
-    let mut m_out_t: Matrix = (init_vals, m.0.len() / m.1);
+pub fn column_subset<T: Copy>(m: &(Vec<T>, usize), cols: &[usize]) -> (Vec<T>, usize) {
+    let n_rows: usize = m.0.len() / m.1;
+    let mut m_out: (Vec<T>, usize) = (Vec::with_capacity(n_rows * cols.len()), cols.len());
 
-    for idx in 1..cols.len() {
-        let mut this_col = get_column(m, cols.get(idx).unwrap().to_owned());
-        // modify dim, transposing the col:
-        this_col.1 = this_col.0.len();
-        append_rows(&mut m_out_t, &this_col);
+    for row in 0..n_rows {
+        for col in cols.iter() {
+            m_out.0.push(m.0.get(row * m.1 + col).unwrap().to_owned());
+        }
     }
 
-    transpose(&m_out_t)
+    m_out
 }
 
-// loads a dataset formatted like GAMETES 2.0 output
-// final vec<string> in tuple is the header key
-pub fn load_data(fp: &String) -> (Matrix, Matrix, Vec<String>) {
-    let mut file = File::open(fp).unwrap();
-    let mut str_in = String::new();
-
-    file.read_to_string(&mut str_in).unwrap_or_else(|why| {
-        panic!("Could not read data in: {}", why);
-    });
-
-    let mut x: Matrix = (Vec::new(), 0);
-    let mut y: Matrix = (Vec::new(), 1);
-    let mut header: Vec<String> = Vec::new();
-
-    for line in str_in.split("\n") {
-        if line.len() > 0 {
-            if line.starts_with("N") {
-                header = line.split_whitespace().map(|s| s.to_owned()).collect();
-            } else {
-                let mut vals: Vec<&str> = line.split_whitespace().collect();
+// returns the rows of m in the order given
+pub fn row_subset<T: Copy>(m: &(Vec<T>, usize), rows: &[usize]) -> (Vec<T>, usize) {
+    let mut m_out: (Vec<T>, usize) = (Vec::with_capacity(rows.len() * m.1), m.1);
 
-                // check dim
-                if x.1 == 0 {
-                    x.1 = vals.len() - 1;
-                }
+    for row in rows.iter() {
+        m_out.0.extend_from_slice(&m.0[row * m.1..(row + 1) * m.1]);
+    }
 
-                let y_val = vals.pop().unwrap();
+    m_out
+}
 
-                y.0.push(y_val.parse::<f64>().unwrap_or_else(|why| {
-                    panic!("Could not parse {} to f64: {}", y_val, why);
-                }));
+// drops the samples missing a genotype in x, and their phenotypes
+pub fn complete_rows(x: &Genotypes, y: &Matrix) -> (Genotypes, Matrix) {
+    let mut x_out: Genotypes = (Vec::with_capacity(x.0.len()), x.1);
+    let mut y_out: Matrix = (Vec::with_capacity(y.0.len()), y.1);
 
-                for val in vals.iter() {
-                    x.0.push(val.parse::<f64>().unwrap_or_else(|why| {
-                        panic!("Could not parse {} to f64: {}", val, why);
-                    }));
-                }
-            }
+    for (row_idx, row) in x.0.chunks(x.1).enumerate() {
+        if row.contains(&MISSING_CODE) {
+            continue;
         }
+
+        x_out.0.extend_from_slice(row);
+        y_out
+            .0
+            .extend_from_slice(&y.0[row_idx * y.1..(row_idx + 1) * y.1]);
     }
 
-    (x, y, header)
+    (x_out, y_out)
+}
+
+// number of samples with a genotype for every SNP in path, the samples
+// a statistic on path is computed from
+pub fn count_complete(x: &Genotypes, path: &[usize]) -> usize {
+    x.0.chunks(x.1)
+        .filter(|row| path.iter().all(|col| row[*col] != MISSING_CODE))
+        .count()
+}
+
+// loads a dataset formatted like GAMETES 2.0 output, or a binary
+// dataset written by convert
+// final vec<string> in tuple is the header key
+pub fn load_data(fp: &str) -> Result<(Genotypes, Matrix, Vec<String>)> {
+    load_data_with_progress(fp, &LoadOptions::default(), |_| {})
+}
+
+// writes a dataset in the format load_data reads, with the given
+// delimiter between fields
+pub fn write_data(
+    fp: &str,
+    x: &Genotypes,
+    y: &Matrix,
+    header: &[String],
+    delimiter: &str,
+) -> Result<()> {
+    let io_err = |why| AntsnapError::io(fp, why);
+    let file = File::create(fp).map_err(io_err)?;
+    let mut writer = BufWriter::new(file);
+
+    if !header.is_empty() {
+        writeln!(writer, "{}", header.join(delimiter)).map_err(io_err)?;
+    }
+
+    for (row_idx, row) in x.0.chunks(x.1).enumerate() {
+        let mut vals: Vec<String> = row
+            .iter()
+            .map(|v| {
+                if *v == MISSING_CODE {
+                    "NA".to_owned()
+                } else {
+                    v.to_string()
+                }
+            })
+            .collect();
+        vals.push(y.0.get(row_idx).unwrap().to_string());
+
+        writeln!(writer, "{}", vals.join(delimiter)).map_err(io_err)?;
+    }
+
+    writer.flush().map_err(io_err)
 }
 
 // update the pheromone value for a single pheromone
@@ -321,7 +605,7 @@ pub fn update_pheromones(
 //
 // not a general function. slapped together only for use with this
 // data
-pub fn naive_one_hot(x: &Matrix) -> Matrix {
+pub fn naive_one_hot(x: &Matrix) -> Result<Matrix> {
     let mut m_out: Matrix = (Vec::new(), 0);
 
     for col_idx in 0..x.1 {
@@ -331,7 +615,12 @@ pub fn naive_one_hot(x: &Matrix) -> Matrix {
             let element_idx: usize = col_idx * x.1 + row_idx;
             let element: &Element = x.0.get(element_idx).unwrap();
 
-            if element == &0.0 {
+            // missing genotypes have no genotype column set
+            if is_missing(*element) {
+                new_cols.0.push(0.0);
+                new_cols.0.push(0.0);
+                new_cols.0.push(0.0);
+            } else if element == &0.0 {
                 new_cols.0.push(1.0);
                 new_cols.0.push(0.0);
                 new_cols.0.push(0.0);
@@ -349,11 +638,11 @@ pub fn naive_one_hot(x: &Matrix) -> Matrix {
         if col_idx == 0 {
             m_out = new_cols;
         } else {
-            m_out = append_columns(&m_out, &new_cols);
+            m_out = append_columns(&m_out, &new_cols)?;
         }
     }
 
-    m_out
+    Ok(m_out)
 }
 
 // get the interactive term value for a given x matrix
@@ -376,9 +665,10 @@ pub fn get_interactive_term(x: &Matrix) -> Matrix {
     m_out
 }
 
-// build a contingency table for Chi square test
-// NOTE this works only for 3-snp combos
-pub fn build_contingency_table(x: &Matrix, y: &Matrix) -> Matrix {
+// build a contingency table for Chi square test. the first row holds
+// controls and the second cases, columns are the 3^k genotype
+// combinations of the k SNPs in x
+pub fn build_contingency_table(x: &Genotypes, y: &Matrix) -> Matrix {
     let mut contingency_table: Matrix = (
         Vec::with_capacity(3usize.pow(x.1 as u32) * 2),
         3usize.pow(x.1 as u32),
@@ -391,15 +681,19 @@ pub fn build_contingency_table(x: &Matrix, y: &Matrix) -> Matrix {
     let n_rows = x.0.len() / x.1;
 
     for row_idx in 0..n_rows {
-        let mut x_vals: Vec<f64> = Vec::with_capacity(x.1);
+        let mut x_vals: Vec<u8> = Vec::with_capacity(x.1);
         for col_idx in 0..x.1 {
             let idx = x.1 * row_idx + col_idx;
             x_vals.push(x.0.get(idx).unwrap().to_owned());
         }
 
-        let mut table_idx: usize = (x_vals.get(0).unwrap() * 9.0
-            + x_vals.get(1).unwrap() * 3.0
-            + x_vals.get(2).unwrap()) as usize;
+        // samples missing a genotype are left out
+        if x_vals.contains(&MISSING_CODE) {
+            continue;
+        }
+
+        // genotypes as the digits of a base 3 number
+        let mut table_idx: usize = x_vals.iter().fold(0, |acc, val| acc * 3 + *val as usize);
 
         if y.0.get(row_idx).unwrap() == &1.0 {
             table_idx += 3usize.pow(x.1 as u32);
@@ -440,6 +734,13 @@ pub fn get_expected_freqs(table: &Matrix) -> Matrix {
     let total: f64 = table.0.iter().sum();
     let mut table_out: Matrix = (Vec::with_capacity(table.0.len()), table.1);
 
+    // an empty table, e.g. every sample is missing a genotype in the set.
+    // nothing is expected anywhere, rather than 0 / 0
+    if total == 0.0 {
+        table_out.0.resize(table.0.len(), 0.0);
+        return table_out;
+    }
+
     // this is the slow way
     let n_rows = table.0.len() / table.1;
 